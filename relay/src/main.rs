@@ -0,0 +1,92 @@
+//! A tiny relay server for the online race.
+//!
+//! Clients send newline-delimited JSON messages, every message is forwarded
+//! to all the other clients wrapped as `{"from":<client id>,"message":<message>}`.
+//! When a client disconnects the others receive `{"from":<client id>,"message":null}`.
+//! The relay does not look into the messages, so it knows nothing about the game.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, SyncSender};
+use std::sync::{Arc, Mutex};
+
+const DEFAULT_ADDRESS: &str = "127.0.0.1:5151";
+/// Messages queued for a client before it is considered stalled and dropped.
+const QUEUE_LENGTH: usize = 256;
+
+/// The queues of the messages to send to each client, written by their own threads.
+type Clients = Arc<Mutex<HashMap<u64, SyncSender<Arc<str>>>>>;
+
+fn main() -> std::io::Result<()> {
+    let address = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| DEFAULT_ADDRESS.to_owned());
+    let listener = TcpListener::bind(&address)?;
+    println!("Relay listening on {address}");
+
+    let clients: Clients = Default::default();
+    for (id, stream) in (0..).zip(listener.incoming()) {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                eprintln!("Failed to accept a connection: {err}");
+                continue;
+            }
+        };
+        if let Err(err) = stream.set_nodelay(true) {
+            eprintln!("Failed to set up client {id}: {err}");
+            continue;
+        }
+        let writer = match stream.try_clone() {
+            Ok(writer) => writer,
+            Err(err) => {
+                eprintln!("Failed to set up client {id}: {err}");
+                continue;
+            }
+        };
+        let (sender, receiver) = mpsc::sync_channel(QUEUE_LENGTH);
+        clients.lock().unwrap().insert(id, sender);
+        std::thread::spawn(move || write_messages(writer, receiver));
+        println!("Client {id} connected");
+
+        let clients = clients.clone();
+        std::thread::spawn(move || {
+            let reader = BufReader::new(stream);
+            for line in reader.lines() {
+                let line = match line {
+                    Ok(line) => line,
+                    Err(_) => break,
+                };
+                let line = line.trim();
+                if line.is_empty() {
+                    continue;
+                }
+                broadcast(&clients, id, line);
+            }
+            clients.lock().unwrap().remove(&id);
+            broadcast(&clients, id, "null");
+            println!("Client {id} disconnected");
+        });
+    }
+    Ok(())
+}
+
+/// Queues the message for everyone except the sender,
+/// the clients that disconnected or stopped reading are dropped.
+fn broadcast(clients: &Clients, from: u64, message: &str) {
+    let envelope: Arc<str> = format!("{{\"from\":{from},\"message\":{message}}}\n").into();
+    let mut clients = clients.lock().unwrap();
+    clients.retain(|&id, sender| id == from || sender.try_send(envelope.clone()).is_ok());
+}
+
+/// Writes the queued messages to the client until it disconnects or is dropped.
+fn write_messages(mut stream: TcpStream, receiver: mpsc::Receiver<Arc<str>>) {
+    for message in receiver {
+        if stream.write_all(message.as_bytes()).is_err() {
+            break;
+        }
+    }
+    // Also stops the reading thread if the client was dropped
+    let _ = stream.shutdown(std::net::Shutdown::Both);
+}
@@ -5,6 +5,8 @@ use crate::model::*;
 pub struct Assets {
     pub sprites: Sprites,
     pub config: Config,
+    pub achievements: AchievementsConfig,
+    pub levels: LevelsConfig,
     pub sounds: Sounds,
 }
 
@@ -19,21 +21,313 @@ pub struct Animation {
 pub struct Config {
     pub gravity: Vec2<Coord>,
     pub arena_width: Coord,
+    /// How close an obstacle has to pass by the player to count as a near-miss.
+    pub near_miss_margin: Coord,
     pub balloon_pop_time: Time,
+    /// Every that much score a milestone event is emitted.
+    pub score_milestone: Score,
+    /// Which balloon gets popped by the timer.
+    pub pop_policy: PopPolicy,
+    /// How long before popping the balloon starts to inflate.
+    pub pop_warning_time: Time,
     pub player_speed_h: Coord,
     pub player_speed_v_down: Coord,
     pub player_speed_v_up: Coord,
     pub initial_balloons: usize,
-    pub balloon_mass: R32,
     pub player_mass: R32,
     pub balloon_drag: R32,
     pub balloon_attached_drag: R32,
     pub player_drag: R32,
     pub balloon_length: Coord,
     pub balloon_colors: Vec<Rgba<f32>>,
+    pub balloon_kinds: Vec<BalloonKindConfig>,
     pub obstacles: ObstacleConfig,
+    /// How long before entering the arena an incoming obstacle is telegraphed.
+    pub obstacle_warning_time: Time,
+    pub obstacle_movement: MovementConfig,
     pub clouds: ObstacleConfig,
     pub balloons: BalloonsConfig,
+    pub power_ups: PowerUpsConfig,
+    pub platforms: PlatformsConfig,
+    pub scoring: ScoringConfig,
+    pub daily: DailyConfig,
+    pub modes: ModesConfig,
+    pub ground: GroundConfig,
+    pub practice: PracticeConfig,
+    pub multiplayer: MultiplayerConfig,
+    pub net: NetConfig,
+    pub ghosts: GhostsConfig,
+    pub camera: CameraConfig,
+    pub audio: AudioConfig,
+    pub music: MusicConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, geng::Assets)]
+#[asset(json)]
+pub struct MusicConfig {
+    pub volume: R32,
+    /// Time for a layer to fade in or out completely.
+    pub crossfade_time: Time,
+    /// Obstacles within that distance from the player count as danger.
+    pub danger_radius: Coord,
+    /// Volume multiplier while on the death screen.
+    pub death_duck: R32,
+    /// How many last seconds of the pop timer are ticking.
+    pub tick_seconds: i64,
+    pub tick_volume: R32,
+    /// Settings for each of the music stems.
+    pub layers: Vec<MusicLayerConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MusicLayerConfig {
+    /// Height range over which the layer fades in.
+    pub altitude: Option<std::ops::RangeInclusive<Coord>>,
+    /// Number of nearby obstacles at which the layer plays at full volume.
+    pub danger: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, geng::Assets)]
+#[asset(json)]
+pub struct AudioConfig {
+    pub falloff: FalloffConfig,
+    /// Horizontal distance at which a sound is panned completely to one side.
+    pub pan_distance: Coord,
+    /// Pitch change per unit of approach speed.
+    pub doppler_factor: R32,
+    pub plane_pitch: R32,
+    /// Maximum number of obstacle engine sounds playing at once.
+    pub max_engine_voices: usize,
+    pub landing_sounds: Vec<LandingSoundConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LandingSoundConfig {
+    pub surface: Surface,
+    pub sound: LandingSound,
+    pub pitch: R32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LandingSound {
+    Splash,
+    Hit,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FalloffConfig {
+    /// Distance at which the sound becomes silent.
+    pub distance: Coord,
+    pub curve: FalloffCurve,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum FalloffCurve {
+    Linear,
+    Quadratic,
+    InverseQuadratic,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, geng::Assets)]
+#[asset(json)]
+pub struct AchievementsConfig {
+    pub list: Vec<AchievementConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, geng::Assets)]
+#[asset(json)]
+pub struct LevelsConfig {
+    pub list: Vec<Level>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AchievementConfig {
+    pub name: String,
+    pub description: String,
+    pub condition: AchievementCondition,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum AchievementCondition {
+    ReachHeight(Score),
+    HoldBalloons(usize),
+    SurviveWithoutGrabbing(Time),
+    /// Near-miss that many obstacles of the type in total.
+    NearMisses {
+        obstacle: ObstacleType,
+        count: usize,
+    },
+    DieToEachObstacle,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, geng::Assets)]
+#[asset(json)]
+pub struct MultiplayerConfig {
+    /// Horizontal distance between the players at the start.
+    pub spawn_spacing: Coord,
+    /// Time after a steal before the balloon can be stolen back.
+    pub steal_cooldown: Time,
+    /// Give each player their own view instead of a shared camera.
+    pub split_screen: bool,
+    pub player_colors: Vec<Rgba<f32>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, geng::Assets)]
+#[asset(json)]
+pub struct NetConfig {
+    /// Address of the relay server used for online races.
+    pub address: String,
+    /// Time between the updates sent to the other racers.
+    pub send_interval: Time,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, geng::Assets)]
+#[asset(json)]
+pub struct GhostsConfig {
+    /// Opacity of the other racers and the best run replay.
+    pub alpha: R32,
+    /// Time between the recorded positions of a run.
+    pub sample_interval: Time,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, geng::Assets)]
+#[asset(json)]
+pub struct ModesConfig {
+    /// The height to reach in the time attack.
+    pub time_attack_height: Coord,
+    /// How long to survive in the survival mode.
+    pub survival_time: Time,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, geng::Assets)]
+#[asset(json)]
+pub struct GroundConfig {
+    /// Height of the street, the lowest ground everywhere.
+    pub street_height: Coord,
+    /// The buildings of the start area, matching the `start` sprites.
+    pub buildings: Vec<BuildingConfig>,
+    /// Landings slower than that make no sound.
+    pub landing_sound_speed: Coord,
+    /// Landings faster than that kill the caterpillar.
+    pub fatal_impact_speed: Coord,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildingConfig {
+    pub surface: Surface,
+    pub x_min: Coord,
+    pub x_max: Coord,
+    /// Height of the roof.
+    pub height: Coord,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, geng::Assets)]
+#[asset(json)]
+pub struct PracticeConfig {
+    /// Time between the checkpoints of a practice run.
+    pub snapshot_interval: Time,
+    /// How far back a single rewind goes.
+    pub rewind_time: Time,
+    /// How long the checkpoints are kept for.
+    pub history: Time,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, geng::Assets)]
+#[asset(json)]
+pub struct DailyConfig {
+    /// Only the first attempt of the day can set the daily best.
+    pub one_attempt_counts: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, geng::Assets)]
+#[asset(json)]
+pub struct ScoringConfig {
+    pub near_miss_bonus: Score,
+    /// Score for grabbing any free balloon, multiplied by the combo.
+    pub grab_bonus: Score,
+    /// Extra score for grabbing a balloon while flying at least `mid_air_height` high.
+    pub mid_air_bonus: Score,
+    pub mid_air_height: Coord,
+    /// Time to grab the next balloon to continue the combo.
+    pub combo_window: Time,
+    /// Multiplier increase for each chained grab.
+    pub combo_step: R32,
+    pub max_combo_multiplier: R32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, geng::Assets)]
+#[asset(json)]
+pub struct CameraConfig {
+    /// Default vertical field of view.
+    pub fov: Coord,
+    /// Field of view when moving vertically at `zoom_speed`.
+    pub max_fov: Coord,
+    pub zoom_speed: Coord,
+    pub zoom_interpolation: Time,
+    pub interpolation: Time,
+    pub vertical_offset: Coord,
+    /// How much the camera follows the player horizontally, from 0 to 1.
+    pub horizontal_follow: R32,
+    /// How far outside the arena the camera is allowed to see.
+    pub horizontal_margin: Coord,
+    /// Look-ahead in seconds of the player's movement.
+    pub look_ahead: Time,
+    pub max_look_ahead: Coord,
+    pub shake_hit: R32,
+    pub shake_pop: R32,
+    pub shake_decay: R32,
+    pub max_shake: R32,
+    pub death_fov: Coord,
+    pub death_interpolation: Time,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, geng::Assets)]
+#[asset(json)]
+pub struct MovementConfig {
+    pub sine: SineMovementConfig,
+    pub dive: DiveMovementConfig,
+    pub descend_speed: Coord,
+    pub rise_speed: Coord,
+    /// Obstacles farther than that above or below the highest player are removed.
+    pub despawn_distance: Coord,
+    /// Possible movement types for each obstacle type, selected by weight.
+    pub patterns: Vec<MovementPattern>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SineMovementConfig {
+    pub amplitude: Coord,
+    pub frequency: R32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiveMovementConfig {
+    pub speed: Coord,
+    pub duration: Time,
+    pub trigger_distance: Coord,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MovementPattern {
+    pub obstacle_type: ObstacleType,
+    pub movement: MovementType,
+    pub weight: R32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BalloonKindConfig {
+    pub kind: BalloonKind,
+    /// Relative chance of spawning.
+    pub weight: R32,
+    pub mass: R32,
+    pub radius: Coord,
+    pub lift: R32,
+    /// How many obstacle hits the balloon survives before popping.
+    pub durability: usize,
+    /// Score awarded for grabbing the balloon.
+    pub score_bonus: Score,
+    /// Fixed tint of the balloon, a random one from `balloon_colors` is used if not specified.
+    pub color: Option<Rgba<f32>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, geng::Assets)]
@@ -46,6 +340,53 @@ pub struct BalloonsConfig {
     pub max_delay: Time,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, geng::Assets)]
+#[asset(json)]
+pub struct PlatformsConfig {
+    pub spawn_area_width: Coord,
+    pub min_height: Coord,
+    pub above_player: Coord,
+    pub min_delay: Time,
+    pub max_delay: Time,
+    /// How far below the highest player the platforms are removed.
+    pub despawn_below: Coord,
+    /// Whether the balloons keep popping while standing on a platform.
+    pub pop_timer_continues: bool,
+    pub types: Vec<PlatformConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlatformConfig {
+    pub platform_type: PlatformType,
+    pub weight: R32,
+    pub width: Coord,
+    /// Horizontal drifting speed.
+    pub speed: Coord,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, geng::Assets)]
+#[asset(json)]
+pub struct PowerUpsConfig {
+    pub spawn_area_width: Coord,
+    pub min_height: Coord,
+    pub above_player: Coord,
+    pub min_delay: Time,
+    pub max_delay: Time,
+    pub radius: Coord,
+    pub fall_speed: Coord,
+    pub magnet_radius: Coord,
+    pub magnet_acceleration: Coord,
+    pub slow_motion_scale: R32,
+    pub types: Vec<PowerUpConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PowerUpConfig {
+    pub power_up_type: PowerUpType,
+    pub weight: R32,
+    pub duration: Time,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, geng::Assets)]
 #[asset(json)]
 pub struct ObstacleConfig {
@@ -86,12 +427,27 @@ pub struct Sounds {
     pub pop: geng::Sound,
     pub helicopter: geng::Sound,
     pub wind: geng::Sound,
+    pub tick: geng::Sound,
+    #[asset(path = "music/*.wav", range = "0..4")]
+    pub music: Vec<geng::Sound>,
+}
+
+impl Config {
+    pub fn balloon_kind(&self, kind: BalloonKind) -> &BalloonKindConfig {
+        self.balloon_kinds
+            .iter()
+            .find(|config| config.kind == kind)
+            .unwrap_or_else(|| panic!("Balloon kind {kind:?} is not configured"))
+    }
 }
 
 impl Assets {
     pub fn process(&mut self) {
         self.sounds.helicopter.looped = true;
         self.sounds.wind.looped = true;
+        for stem in &mut self.sounds.music {
+            stem.looped = true;
+        }
     }
 }
 
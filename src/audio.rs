@@ -0,0 +1,173 @@
+use super::*;
+
+mod music;
+
+use model::*;
+use music::*;
+
+pub struct Audio {
+    assets: Rc<Assets>,
+    config: AudioConfig,
+    pub volume: f64,
+    pub music: Music,
+    /// Looped engine sounds of the obstacles closest to the player.
+    engines: HashMap<Id, geng::SoundEffect>,
+    wind_sound: geng::SoundEffect,
+}
+
+/// How the sound at some position should be played.
+struct SpatialSound {
+    volume: f64,
+    pan: f64,
+}
+
+impl Audio {
+    pub fn new(assets: &Rc<Assets>) -> Self {
+        Self {
+            assets: assets.clone(),
+            config: assets.config.audio.clone(),
+            volume: 0.5,
+            music: Music::new(assets, &assets.config.music),
+            engines: HashMap::new(),
+            wind_sound: {
+                let mut effect = assets.sounds.wind.effect();
+                effect.set_volume(0.0);
+                effect.play();
+                effect
+            },
+        }
+    }
+
+    fn spatial(&self, model: &Model, position: Vec2<Coord>) -> SpatialSound {
+        let delta = (position - model.focus_player().position).map(|x| x.as_f32());
+        let volume = self.config.falloff.volume(delta.len()) as f64 * self.volume;
+        let pan = (delta.x / self.config.pan_distance.as_f32()).clamp(-1.0, 1.0) as f64;
+        SpatialSound { volume, pan }
+    }
+
+    fn play_sound(&self, model: &Model, sound: &geng::Sound, position: Vec2<Coord>) {
+        let mut effect = sound.effect();
+        let spatial = self.spatial(model, position);
+        effect.set_volume(spatial.volume);
+        effect.set_pan(spatial.pan);
+        effect.play();
+    }
+
+    /// Updates the looped sounds.
+    pub fn update(&mut self, model: &Model, delta_time: f32) {
+        // Wind
+        let volume = (model.focus_player().position.y.max(Coord::ZERO) / r32(20.0))
+            .sqrt()
+            .as_f32()
+            .clamp(0.0, 1.0) as f64
+            * self.volume;
+        self.wind_sound.set_volume(volume);
+
+        self.update_engines(model);
+        self.music.update(model, delta_time);
+    }
+
+    fn update_engines(&mut self, model: &Model) {
+        let listener = model.focus_player();
+        // Only the closest audible obstacles get a voice
+        let mut audible: Vec<(&Obstacle, R32)> = model
+            .obstacles
+            .iter()
+            .map(|obstacle| (obstacle, (obstacle.position - listener.position).len()))
+            .filter(|&(_, distance)| distance < self.config.falloff.distance)
+            .collect();
+        audible.sort_by_key(|&(_, distance)| distance);
+        audible.truncate(self.config.max_engine_voices);
+
+        self.engines.retain(|id, effect| {
+            let keep = audible.iter().any(|(obstacle, _)| obstacle.id == *id);
+            if !keep {
+                effect.stop();
+            }
+            keep
+        });
+
+        for (obstacle, _) in audible {
+            let spatial = self.spatial(model, obstacle.position);
+
+            // Doppler-like pitch shift from the relative velocity
+            let direction = (listener.position - obstacle.position).normalize_or_zero();
+            let approach_speed =
+                Vec2::dot(obstacle.velocity - listener.velocity, direction).as_f32();
+            let (sound, pitch) = match obstacle.obstacle_type {
+                ObstacleType::Plane => (
+                    &self.assets.sounds.helicopter,
+                    self.config.plane_pitch.as_f32(),
+                ),
+                ObstacleType::Helicopter1 | ObstacleType::Helicopter2 => {
+                    (&self.assets.sounds.helicopter, 1.0)
+                }
+            };
+            let speed = (pitch * (1.0 + approach_speed * self.config.doppler_factor.as_f32()))
+                .clamp(0.5, 2.0) as f64;
+
+            let effect = self.engines.entry(obstacle.id).or_insert_with(|| {
+                let mut effect = sound.effect();
+                effect.set_volume(0.0);
+                effect.play();
+                effect
+            });
+            effect.set_volume(spatial.volume);
+            effect.set_pan(spatial.pan);
+            effect.set_speed(speed);
+        }
+    }
+
+    pub fn handle_event(&mut self, model: &Model, event: &Event) {
+        let sounds = &self.assets.sounds;
+        match *event {
+            Event::BalloonPopped { position, .. } => {
+                self.play_sound(model, &sounds.pop, position);
+            }
+            Event::BalloonGrabbed { .. }
+            | Event::BalloonStolen { .. }
+            | Event::PowerUpCollected { .. }
+            | Event::AchievementUnlocked { .. } => {
+                if let Some(nya) = sounds.nya.choose(&mut global_rng()) {
+                    nya.play();
+                }
+            }
+            Event::PlayerHit { .. } | Event::PlayerFell { .. } => {
+                sounds.hit.play();
+            }
+            Event::PlayerLanded { surface, .. } => {
+                let config = self
+                    .config
+                    .landing_sounds
+                    .iter()
+                    .find(|config| config.surface == surface);
+                if let Some(config) = config {
+                    let sound = match config.sound {
+                        LandingSound::Splash => &sounds.splash,
+                        LandingSound::Hit => &sounds.hit,
+                    };
+                    let mut effect = sound.effect();
+                    effect.set_volume(self.volume);
+                    effect.set_speed(config.pitch.as_f32() as f64);
+                    effect.play();
+                }
+            }
+            Event::ObstacleSpawned { .. }
+            | Event::ScoreMilestone { .. }
+            | Event::NearMiss { .. }
+            | Event::BonusScore { .. } => {}
+        }
+    }
+}
+
+impl FalloffConfig {
+    /// Returns the volume multiplier of a sound at the given distance.
+    pub fn volume(&self, distance: f32) -> f32 {
+        let t = (distance / self.distance.as_f32()).clamp(0.0, 1.0);
+        match self.curve {
+            FalloffCurve::Linear => 1.0 - t,
+            FalloffCurve::Quadratic => 1.0 - t.sqr(),
+            FalloffCurve::InverseQuadratic => (1.0 - t).sqr(),
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use super::*;
+
+/// Background music made of stems that fade in and out depending on the game state.
+pub struct Music {
+    assets: Rc<Assets>,
+    config: MusicConfig,
+    pub volume: f64,
+    pub muted: bool,
+    stems: Vec<Stem>,
+    /// The last whole second of the pop timer, used to play the ticks.
+    last_pop_second: Option<i64>,
+}
+
+struct Stem {
+    effect: geng::SoundEffect,
+    volume: f32,
+}
+
+impl Music {
+    pub fn new(assets: &Rc<Assets>, config: &MusicConfig) -> Self {
+        // Start all stems at once so that they stay in sync
+        let stems = assets
+            .sounds
+            .music
+            .iter()
+            .map(|sound| {
+                let mut effect = sound.effect();
+                effect.set_volume(0.0);
+                effect.play();
+                Stem {
+                    effect,
+                    volume: 0.0,
+                }
+            })
+            .collect();
+        Self {
+            assets: assets.clone(),
+            config: config.clone(),
+            volume: config.volume.as_f32() as f64,
+            muted: false,
+            stems,
+            last_pop_second: None,
+        }
+    }
+
+    pub fn update(&mut self, model: &Model, delta_time: f32) {
+        let config = &self.config;
+        let player = model.focus_player();
+        let height = player.position.y.as_f32();
+        let danger = model
+            .obstacles
+            .iter()
+            .filter(|obstacle| (obstacle.position - player.position).len() < config.danger_radius)
+            .count();
+        let duck = if player.alive && !player.balloons.is_empty() {
+            1.0
+        } else {
+            config.death_duck.as_f32()
+        };
+        let volume = if self.muted { 0.0 } else { self.volume };
+
+        for (stem, layer) in self.stems.iter_mut().zip(&config.layers) {
+            let mut target = duck;
+            if let Some(altitude) = &layer.altitude {
+                let (start, end) = (altitude.start().as_f32(), altitude.end().as_f32());
+                let t = (height - start) / (end - start);
+                target *= t.clamp(0.0, 1.0);
+            }
+            if let Some(danger_obstacles) = layer.danger {
+                target *= (danger as f32 / danger_obstacles as f32).min(1.0);
+            }
+            let max_change = delta_time / config.crossfade_time.as_f32();
+            stem.volume += (target - stem.volume).clamp(-max_change, max_change);
+            stem.effect.set_volume(stem.volume as f64 * volume);
+        }
+
+        // Tick in sync with the pop timer
+        let pop_second = player.next_pop.as_f32().ceil() as i64;
+        if let Some(last) = self.last_pop_second {
+            let countdown = pop_second < last && pop_second <= config.tick_seconds;
+            let popped = pop_second > last;
+            if (countdown || popped) && player.alive && !self.muted {
+                let mut effect = self.assets.sounds.tick.effect();
+                effect.set_volume(config.tick_volume.as_f32() as f64 * volume);
+                effect.play();
+            }
+        }
+        self.last_pop_second = Some(pop_second);
+    }
+}
@@ -0,0 +1,128 @@
+use super::*;
+
+use model::*;
+use std::collections::VecDeque;
+
+/// Snapshots of the whole model taken regularly during a practice run,
+/// so the run can be rewound to an earlier moment.
+pub struct Checkpoints {
+    /// Time since the start of the run.
+    time: Time,
+    next_snapshot: Time,
+    /// The snapshots with the time they were taken at, oldest first.
+    snapshots: VecDeque<(Time, Model)>,
+}
+
+impl Checkpoints {
+    pub fn new() -> Self {
+        Self {
+            time: Time::ZERO,
+            next_snapshot: Time::ZERO,
+            snapshots: VecDeque::new(),
+        }
+    }
+
+    pub fn update(&mut self, model: &Model, delta_time: Time) {
+        let config = &model.config.practice;
+        self.time += delta_time;
+        if self.next_snapshot <= self.time {
+            self.next_snapshot = self.time + config.snapshot_interval;
+            self.snapshots.push_back((self.time, model.clone()));
+        }
+        while let Some(&(time, _)) = self.snapshots.front() {
+            if time >= self.time - config.history {
+                break;
+            }
+            self.snapshots.pop_front();
+        }
+    }
+
+    /// The latest snapshot taken at least `rewind_time` ago, or the oldest one.
+    /// Rewinding again goes further back.
+    pub fn rewind(&mut self, rewind_time: Time) -> Option<Model> {
+        while self.snapshots.len() > 1 {
+            match self.snapshots.back() {
+                Some(&(time, _)) if time > self.time - rewind_time => {
+                    self.snapshots.pop_back();
+                }
+                _ => break,
+            }
+        }
+        let (time, model) = self.snapshots.back()?;
+        self.time = *time;
+        self.next_snapshot = *time + model.config.practice.snapshot_interval;
+        Some(model.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model() -> Model {
+        let mut config: Config = serde_json::from_str(include_str!("../static/config.json"))
+            .expect("Failed to parse the config");
+        config.practice = PracticeConfig {
+            snapshot_interval: r32(1.0),
+            rewind_time: r32(3.0),
+            history: r32(5.0),
+        };
+        Model::new(
+            config,
+            Vec::new(),
+            RunSetup {
+                mode: GameMode::Practice,
+                multiplayer: Multiplayer::Single,
+            },
+        )
+    }
+
+    /// Runs the checkpoints for the frames of half a second,
+    /// the score of each snapshot is the frame it was taken at.
+    fn run(checkpoints: &mut Checkpoints, model: &mut Model, frames: std::ops::Range<u64>) {
+        for frame in frames {
+            model.score = frame;
+            checkpoints.update(model, r32(0.5));
+        }
+    }
+
+    #[test]
+    fn rewinding_goes_further_back_and_stops_at_the_oldest() {
+        let mut model = model();
+        let mut checkpoints = Checkpoints::new();
+        run(&mut checkpoints, &mut model, 0..11);
+        let score = |model: Option<Model>| model.map(|model| model.score);
+        assert_eq!(score(checkpoints.rewind(r32(3.0))), Some(4));
+        assert_eq!(score(checkpoints.rewind(r32(3.0))), Some(0));
+        assert_eq!(score(checkpoints.rewind(r32(3.0))), Some(0));
+    }
+
+    #[test]
+    fn snapshots_continue_after_a_rewind() {
+        let mut model = model();
+        let mut checkpoints = Checkpoints::new();
+        run(&mut checkpoints, &mut model, 0..11);
+        checkpoints.rewind(r32(3.0));
+        run(&mut checkpoints, &mut model, 100..104);
+        assert_eq!(
+            checkpoints.rewind(r32(1.0)).map(|model| model.score),
+            Some(101)
+        );
+    }
+
+    #[test]
+    fn old_snapshots_are_dropped() {
+        let mut model = model();
+        let mut checkpoints = Checkpoints::new();
+        run(&mut checkpoints, &mut model, 0..21);
+        assert_eq!(
+            checkpoints.rewind(r32(100.0)).map(|model| model.score),
+            Some(10)
+        );
+    }
+
+    #[test]
+    fn nothing_to_rewind_to() {
+        assert!(Checkpoints::new().rewind(r32(3.0)).is_none());
+    }
+}
@@ -0,0 +1,446 @@
+use super::*;
+
+use model::*;
+
+/// File the levels are loaded from, relative to the static directory.
+const LEVELS_FILE: &str = "levels.json";
+
+/// How far below an obstacle a player has to be for it to spawn.
+const OBSTACLE_LEAD: f32 = 6.0;
+/// How far above a balloon a player has to be for it to spawn.
+const BALLOON_LEAD: f32 = 4.0;
+/// Where the obstacles are shown, they enter the arena from its edges.
+const OBSTACLE_PREVIEW_X: f32 = 7.5;
+/// How close to an entry the cursor has to be to pick it.
+const PICK_RADIUS: f32 = 0.6;
+const SCROLL_SPEED: f32 = 1.0;
+
+const OBSTACLE_TYPES: [ObstacleType; 3] = [
+    ObstacleType::Plane,
+    ObstacleType::Helicopter1,
+    ObstacleType::Helicopter2,
+];
+const MOVEMENT_TYPES: [Option<MovementType>; 5] = [
+    None,
+    Some(MovementType::Sine),
+    Some(MovementType::Dive),
+    Some(MovementType::Descend),
+    Some(MovementType::Rise),
+];
+const BALLOON_KINDS: [BalloonKind; 5] = [
+    BalloonKind::Normal,
+    BalloonKind::HeavyDuty,
+    BalloonKind::Helium,
+    BalloonKind::Golden,
+    BalloonKind::Lead,
+];
+const CLOUD_TYPES: [CloudType; 3] = [CloudType::Cloud0, CloudType::Cloud1, CloudType::Cloud2];
+
+/// Places the entries of a level with the mouse.
+pub struct Editor {
+    pub level: Level,
+    /// The level as it was last saved, to warn about unsaved changes.
+    saved: Level,
+    /// Escape was pressed once with unsaved changes, pressing it again discards them.
+    pub confirm_exit: bool,
+    /// Index of the level in the level list, equal to its length for a new level.
+    pub index: usize,
+    /// Height of the center of the view.
+    pub view_height: f32,
+    pub fov: f32,
+    pub framebuffer_size: Vec2<f32>,
+    /// Cursor position in the world.
+    pub cursor: Vec2<f32>,
+    pub tool: Tool,
+    pub selected: Option<Selection>,
+    dragging: bool,
+    /// Parameters of the newly placed entries.
+    obstacle_template: LevelObstacle,
+    balloon_template: LevelBalloon,
+    cloud_template: LevelCloud,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tool {
+    Obstacle,
+    Balloon,
+    Cloud,
+}
+
+/// An entry of the level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Selection {
+    Obstacle(usize),
+    Balloon(usize),
+    Cloud(usize),
+}
+
+/// What the game has to do after an editor input.
+pub enum EditorAction {
+    Exit,
+    Save,
+    /// Play the level starting from the height.
+    Playtest(Coord),
+}
+
+impl Editor {
+    pub fn new(index: usize, level: Level, fov: f32) -> Self {
+        Self {
+            saved: level.clone(),
+            level,
+            confirm_exit: false,
+            index,
+            view_height: fov / 2.0 - 3.0,
+            fov,
+            framebuffer_size: vec2(1.0, 1.0),
+            cursor: Vec2::ZERO,
+            tool: Tool::Obstacle,
+            selected: None,
+            dragging: false,
+            obstacle_template: LevelObstacle {
+                trigger: Trigger::Start,
+                obstacle_type: ObstacleType::Plane,
+                side: Side::Left,
+                height: Coord::ZERO,
+                speed: r32(2.5),
+                movement: None,
+            },
+            balloon_template: LevelBalloon {
+                trigger: Trigger::Start,
+                kind: BalloonKind::Normal,
+                position: Vec2::ZERO,
+            },
+            cloud_template: LevelCloud {
+                trigger: Trigger::Start,
+                cloud_type: CloudType::Cloud0,
+                position: Vec2::ZERO,
+                speed: r32(0.3),
+            },
+        }
+    }
+
+    /// A new empty level.
+    pub fn new_level(index: usize) -> Level {
+        Level {
+            name: format!("Level {}", index + 1),
+            finish_height: r32(50.0),
+            star_times: vec![r32(60.0), r32(40.0)],
+            obstacles: Vec::new(),
+            balloons: Vec::new(),
+            clouds: Vec::new(),
+        }
+    }
+
+    pub fn is_modified(&self) -> bool {
+        self.level != self.saved
+    }
+
+    pub fn camera(&self) -> geng::Camera2d {
+        geng::Camera2d {
+            center: vec2(0.0, self.view_height),
+            fov: self.fov,
+            rotation: 0.0,
+        }
+    }
+
+    fn screen_to_world(&self, position: Vec2<f32>) -> Vec2<f32> {
+        let scale = self.framebuffer_size.y / self.fov;
+        vec2(0.0, self.view_height) + (position - self.framebuffer_size / 2.0) / scale
+    }
+
+    /// Positions of all entries as they are shown in the editor.
+    pub fn entries(&self) -> Vec<(Selection, Vec2<f32>)> {
+        let obstacles = self
+            .level
+            .obstacles
+            .iter()
+            .enumerate()
+            .map(|(i, obstacle)| {
+                let x = OBSTACLE_PREVIEW_X * obstacle.side.sign().as_f32();
+                (Selection::Obstacle(i), vec2(x, obstacle.height.as_f32()))
+            });
+        let balloons = self
+            .level
+            .balloons
+            .iter()
+            .enumerate()
+            .map(|(i, balloon)| (Selection::Balloon(i), balloon.position.map(|x| x.as_f32())));
+        let clouds = self
+            .level
+            .clouds
+            .iter()
+            .enumerate()
+            .map(|(i, cloud)| (Selection::Cloud(i), cloud.position.map(|x| x.as_f32())));
+        obstacles.chain(balloons).chain(clouds).collect()
+    }
+
+    /// The entry under the cursor.
+    fn pick(&self) -> Option<Selection> {
+        self.entries()
+            .into_iter()
+            .filter(|(_, position)| (*position - self.cursor).len() < PICK_RADIUS)
+            .min_by_key(|(_, position)| r32((*position - self.cursor).len()))
+            .map(|(selection, _)| selection)
+    }
+
+    pub fn handle_event(&mut self, event: geng::Event) -> Option<EditorAction> {
+        match event {
+            geng::Event::MouseMove { position, .. } => {
+                self.cursor = self.screen_to_world(position.map(|x| x as f32));
+                if self.dragging {
+                    if let Some(selection) = self.selected {
+                        self.move_entry(selection, self.cursor);
+                    }
+                }
+            }
+            geng::Event::MouseDown { position, button } => {
+                self.cursor = self.screen_to_world(position.map(|x| x as f32));
+                match button {
+                    geng::MouseButton::Left => {
+                        let selection = self.pick().unwrap_or_else(|| self.place());
+                        self.selected = Some(selection);
+                        self.dragging = true;
+                    }
+                    geng::MouseButton::Right => {
+                        if let Some(selection) = self.pick() {
+                            self.remove(selection);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            geng::Event::MouseUp { .. } => self.dragging = false,
+            geng::Event::Wheel { delta } => self.scroll(delta as f32 / 100.0),
+            geng::Event::KeyDown { key } => return self.handle_key(key),
+            _ => {}
+        }
+        None
+    }
+
+    fn handle_key(&mut self, key: geng::Key) -> Option<EditorAction> {
+        let confirm_exit = std::mem::take(&mut self.confirm_exit);
+        match key {
+            geng::Key::Escape => {
+                if self.is_modified() && !confirm_exit {
+                    self.confirm_exit = true;
+                    return None;
+                }
+                return Some(EditorAction::Exit);
+            }
+            geng::Key::S => {
+                self.saved = self.level.clone();
+                return Some(EditorAction::Save);
+            }
+            geng::Key::P => {
+                let height = (self.view_height - self.fov / 2.0 + 1.0).max(0.0);
+                return Some(EditorAction::Playtest(r32(height)));
+            }
+            geng::Key::Num1 => self.set_tool(Tool::Obstacle),
+            geng::Key::Num2 => self.set_tool(Tool::Balloon),
+            geng::Key::Num3 => self.set_tool(Tool::Cloud),
+            geng::Key::Up => self.scroll(SCROLL_SPEED),
+            geng::Key::Down => self.scroll(-SCROLL_SPEED),
+            geng::Key::H => self.level.finish_height = r32(self.cursor.y.max(1.0)),
+            geng::Key::Delete | geng::Key::Backspace => {
+                if let Some(selection) = self.selected {
+                    self.remove(selection);
+                }
+            }
+            geng::Key::T => {
+                if let Some(obstacle) = self.edited_obstacle() {
+                    obstacle.obstacle_type = next(&OBSTACLE_TYPES, obstacle.obstacle_type);
+                } else if let Some(balloon) = self.edited_balloon() {
+                    balloon.kind = next(&BALLOON_KINDS, balloon.kind);
+                } else if let Some(cloud) = self.edited_cloud() {
+                    cloud.cloud_type = next(&CLOUD_TYPES, cloud.cloud_type);
+                }
+            }
+            geng::Key::V => {
+                if let Some(obstacle) = self.edited_obstacle() {
+                    obstacle.movement = next(&MOVEMENT_TYPES, obstacle.movement);
+                }
+            }
+            geng::Key::F => {
+                if let Some(obstacle) = self.edited_obstacle() {
+                    obstacle.side = match obstacle.side {
+                        Side::Left => Side::Right,
+                        Side::Right => Side::Left,
+                    };
+                } else if let Some(cloud) = self.edited_cloud() {
+                    cloud.speed = -cloud.speed;
+                }
+            }
+            geng::Key::Q => self.change_speed(r32(-0.5)),
+            geng::Key::E => self.change_speed(r32(0.5)),
+            _ => {}
+        }
+        None
+    }
+
+    fn set_tool(&mut self, tool: Tool) {
+        self.tool = tool;
+        self.selected = None;
+    }
+
+    fn scroll(&mut self, delta: f32) {
+        self.view_height = (self.view_height + delta).max(self.fov / 2.0 - 5.0);
+        self.cursor.y += delta;
+    }
+
+    fn change_speed(&mut self, delta: Coord) {
+        if let Some(obstacle) = self.edited_obstacle() {
+            obstacle.speed = (obstacle.speed + delta).max(r32(0.5));
+        } else if let Some(cloud) = self.edited_cloud() {
+            // Keep the direction
+            let speed = (cloud.speed.abs() + delta).max(Coord::ZERO);
+            cloud.speed = if cloud.speed < Coord::ZERO {
+                -speed
+            } else {
+                speed
+            };
+        }
+    }
+
+    /// The selected obstacle, or the template for the new ones if nothing is selected.
+    fn edited_obstacle(&mut self) -> Option<&mut LevelObstacle> {
+        match self.selected {
+            Some(Selection::Obstacle(i)) => self.level.obstacles.get_mut(i),
+            None if self.tool == Tool::Obstacle => Some(&mut self.obstacle_template),
+            _ => None,
+        }
+    }
+
+    fn edited_balloon(&mut self) -> Option<&mut LevelBalloon> {
+        match self.selected {
+            Some(Selection::Balloon(i)) => self.level.balloons.get_mut(i),
+            None if self.tool == Tool::Balloon => Some(&mut self.balloon_template),
+            _ => None,
+        }
+    }
+
+    fn edited_cloud(&mut self) -> Option<&mut LevelCloud> {
+        match self.selected {
+            Some(Selection::Cloud(i)) => self.level.clouds.get_mut(i),
+            None if self.tool == Tool::Cloud => Some(&mut self.cloud_template),
+            _ => None,
+        }
+    }
+
+    /// Places a new entry of the current tool at the cursor.
+    fn place(&mut self) -> Selection {
+        let selection = match self.tool {
+            Tool::Obstacle => {
+                self.level.obstacles.push(self.obstacle_template.clone());
+                Selection::Obstacle(self.level.obstacles.len() - 1)
+            }
+            Tool::Balloon => {
+                self.level.balloons.push(self.balloon_template.clone());
+                Selection::Balloon(self.level.balloons.len() - 1)
+            }
+            Tool::Cloud => {
+                self.level.clouds.push(self.cloud_template.clone());
+                Selection::Cloud(self.level.clouds.len() - 1)
+            }
+        };
+        self.move_entry(selection, self.cursor);
+        selection
+    }
+
+    /// Moves the entry, its height trigger follows it.
+    fn move_entry(&mut self, selection: Selection, position: Vec2<f32>) {
+        let position = position.map(r32);
+        match selection {
+            Selection::Obstacle(i) => {
+                let obstacle = &mut self.level.obstacles[i];
+                obstacle.side = if position.x < Coord::ZERO {
+                    Side::Left
+                } else {
+                    Side::Right
+                };
+                obstacle.height = position.y;
+                obstacle.trigger = Trigger::Height(position.y - r32(OBSTACLE_LEAD));
+            }
+            Selection::Balloon(i) => {
+                let balloon = &mut self.level.balloons[i];
+                balloon.position = position;
+                balloon.trigger = Trigger::Height(position.y + r32(BALLOON_LEAD));
+            }
+            Selection::Cloud(i) => self.level.clouds[i].position = position,
+        }
+    }
+
+    fn remove(&mut self, selection: Selection) {
+        match selection {
+            Selection::Obstacle(i) => {
+                self.level.obstacles.remove(i);
+            }
+            Selection::Balloon(i) => {
+                self.level.balloons.remove(i);
+            }
+            Selection::Cloud(i) => {
+                self.level.clouds.remove(i);
+            }
+        }
+        self.selected = None;
+        self.dragging = false;
+    }
+
+    /// Describes the parameters of the selected entry or the current tool.
+    pub fn describe(&self) -> String {
+        let obstacle = |obstacle: &LevelObstacle| {
+            format!(
+                "{:?} from the {:?}, speed {:.1}, {}",
+                obstacle.obstacle_type,
+                obstacle.side,
+                obstacle.speed.as_f32(),
+                match obstacle.movement {
+                    Some(movement) => format!("{movement:?}"),
+                    None => "Linear".to_owned(),
+                }
+            )
+        };
+        let balloon = |balloon: &LevelBalloon| format!("{:?} balloon", balloon.kind);
+        let cloud = |cloud: &LevelCloud| {
+            format!("{:?}, speed {:.1}", cloud.cloud_type, cloud.speed.as_f32())
+        };
+        match self.selected {
+            Some(Selection::Obstacle(i)) => {
+                format!("Selected: {}", obstacle(&self.level.obstacles[i]))
+            }
+            Some(Selection::Balloon(i)) => {
+                format!("Selected: {}", balloon(&self.level.balloons[i]))
+            }
+            Some(Selection::Cloud(i)) => format!("Selected: {}", cloud(&self.level.clouds[i])),
+            None => match self.tool {
+                Tool::Obstacle => format!("New: {}", obstacle(&self.obstacle_template)),
+                Tool::Balloon => format!("New: {}", balloon(&self.balloon_template)),
+                Tool::Cloud => format!("New: {}", cloud(&self.cloud_template)),
+            },
+        }
+    }
+}
+
+/// The item after the given one, wrapping around.
+fn next<T: Copy + PartialEq>(items: &[T], current: T) -> T {
+    let index = items.iter().position(|&item| item == current).unwrap_or(0);
+    items[(index + 1) % items.len()]
+}
+
+/// Writes the levels back to the level file,
+/// on the web it is printed to the log instead.
+pub fn save_levels(levels: &[Level]) {
+    let config = LevelsConfig {
+        list: levels.to_vec(),
+    };
+    let json = serde_json::to_string_pretty(&config).expect("Failed to serialize the levels");
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let path = static_path().join(LEVELS_FILE);
+        match std::fs::write(&path, &json) {
+            Ok(()) => info!("Saved the levels to {}", path.display()),
+            Err(err) => error!("Failed to save the levels: {err}"),
+        }
+    }
+    #[cfg(target_arch = "wasm32")]
+    info!("{LEVELS_FILE}:\n{json}");
+}
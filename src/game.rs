@@ -1,52 +1,141 @@
 use super::*;
 
+use audio::Audio;
+use checkpoints::Checkpoints;
+use editor::{Editor, EditorAction};
 use model::*;
+use net::NetClient;
 use render::Render;
+use replay::{Replay, ReplayRecorder};
+use statistics::{LifetimeStatistics, RunStatistics};
 
 pub struct Game {
     geng: Geng,
     assets: Rc<Assets>,
     render: Render,
+    audio: Audio,
+    statistics: RunStatistics,
+    lifetime_statistics: LifetimeStatistics,
     model: Model,
+    screen: Screen,
+    /// Connection to the relay server for online races.
+    net: Option<NetClient>,
+    replay: ReplayRecorder,
+    /// The run was resumed from a save or a snapshot,
+    /// so its replay is missing the start.
+    resumed: bool,
+    /// Rewind points of the practice mode.
+    checkpoints: Checkpoints,
+    best_replay: Option<Replay>,
+    show_best_run: bool,
+    /// The level list, updated when the editor saves.
+    levels: Vec<Level>,
+    /// The level highlighted on the level select screen,
+    /// the one past the last level stands for a new level.
+    selected_level: usize,
+    level_records: LevelRecords,
+    /// The mode highlighted in the mode menu.
+    selected_mode: usize,
+    editor: Option<Editor>,
+    /// The height the edited level is play-tested from.
+    playtest_height: Option<Coord>,
+}
+
+/// An overlay shown on top of the game, pausing it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Screen {
+    Game,
+    Achievements,
+    Statistics,
+    Levels,
+    Modes,
+    /// The level editor, the game is hidden.
+    Editor,
 }
 
 impl Game {
     pub fn new(geng: &Geng, assets: &Rc<Assets>) -> Self {
+        let saved_run = Model::load_run(assets.achievements.list.clone());
         Self {
             geng: geng.clone(),
             assets: assets.clone(),
             render: Render::new(geng, assets),
-            model: Model::new(assets.config.clone(), assets),
+            audio: Audio::new(assets),
+            statistics: RunStatistics::new(),
+            lifetime_statistics: LifetimeStatistics::load(),
+            resumed: saved_run.is_some(),
+            model: saved_run.unwrap_or_else(|| {
+                Model::new(
+                    assets.config.clone(),
+                    assets.achievements.list.clone(),
+                    RunSetup {
+                        mode: GameMode::Endless,
+                        multiplayer: Multiplayer::Single,
+                    },
+                )
+            }),
+            screen: Screen::Game,
+            net: None,
+            replay: ReplayRecorder::new(),
+            checkpoints: Checkpoints::new(),
+            best_replay: Replay::load(),
+            show_best_run: true,
+            levels: assets.levels.list.clone(),
+            selected_level: 0,
+            level_records: LevelRecords::load(),
+            selected_mode: 0,
+            editor: None,
+            playtest_height: None,
         }
     }
 
     fn control(&mut self, delta_time: Time) {
-        let geng = &self.geng;
-        fn is_pressed(geng: &Geng, keys: impl IntoIterator<Item = geng::Key>) -> bool {
-            let window = geng.window();
-            keys.into_iter().any(|key| window.is_key_pressed(key))
-        }
         use geng::Key;
+        const BINDINGS: [[Key; 4]; 2] = [
+            [Key::A, Key::D, Key::S, Key::W],
+            [Key::Left, Key::Right, Key::Down, Key::Up],
+        ];
+
+        let players = self.model.players.len();
+        for index in 0..players {
+            // With several players each one gets their own keys
+            let keys = if players == 1 {
+                &BINDINGS[..]
+            } else {
+                std::slice::from_ref(&BINDINGS[index % BINDINGS.len()])
+            };
+            self.control_player(index, keys, delta_time);
+        }
+    }
 
-        if !self.model.player.alive {
-            self.model.player_control_velocity = Vec2::ZERO;
+    /// Controls the player with the given (left, right, down, up) key sets.
+    fn control_player(&mut self, index: usize, keys: &[[geng::Key; 4]], delta_time: Time) {
+        let window = self.geng.window();
+        let is_pressed = |direction: usize| {
+            keys.iter()
+                .any(|keys| window.is_key_pressed(keys[direction]))
+        };
+
+        let player = &mut self.model.players[index];
+        if !player.alive {
+            player.control_velocity = Vec2::ZERO;
             return;
         }
         let mut direction: Vec2<i32> = Vec2::ZERO;
-        if is_pressed(geng, [Key::A, Key::Left]) {
+        if is_pressed(0) {
             direction.x -= 1;
         }
-        if is_pressed(geng, [Key::D, Key::Right]) {
+        if is_pressed(1) {
             direction.x += 1;
         }
-        if is_pressed(geng, [Key::S, Key::Down]) {
+        if is_pressed(2) {
             direction.y -= 1;
         }
-        if is_pressed(geng, [Key::W, Key::Up]) {
+        if is_pressed(3) {
             direction.y += 1;
         }
         let config = &self.model.config;
-        let speed_y = if self.model.player.balloons.is_empty() {
+        let speed_y = if player.balloons.is_empty() {
             Coord::ZERO
         } else if direction.y < 0 {
             config.player_speed_v_down
@@ -56,32 +145,442 @@ impl Game {
         let speed = vec2(config.player_speed_h, speed_y);
         let target_speed = direction.map(|x| r32(x as f32)) * speed;
         let acc = r32(10.0);
-        self.model.player_control_velocity += (target_speed - self.model.player_control_velocity)
-            .clamp_len(Coord::ZERO..=acc * delta_time);
+        player.control_velocity +=
+            (target_speed - player.control_velocity).clamp_len(Coord::ZERO..=acc * delta_time);
+    }
+
+    /// Adds the current run to the lifetime statistics.
+    /// Rewound, play-tested and resumed runs are not comparable to the real ones.
+    fn record_run(&mut self) {
+        let playtest = self
+            .model
+            .level
+            .as_ref()
+            .map_or(false, |level| !level.record);
+        if self.model.setup.mode == GameMode::Practice || playtest || self.resumed {
+            return;
+        }
+        self.lifetime_statistics.record(&self.statistics);
+        self.lifetime_statistics.save();
+    }
+
+    /// Keeps the replay of the run if it beats the best endless run.
+    fn save_replay(&mut self) {
+        let replay = match self.replay.finish() {
+            Some(replay) => replay,
+            None => return,
+        };
+        if !self.is_best_run_mode() || !self.model.counts_for_record || self.resumed {
+            return;
+        }
+        let best_score = self.best_replay.as_ref().map(|best| best.score);
+        if best_score.map_or(true, |best| replay.score > best) {
+            replay.save();
+            self.best_replay = Some(replay);
+        }
+    }
+
+    /// The best run is only recorded and shown in the solo endless mode.
+    fn is_best_run_mode(&self) -> bool {
+        self.model.setup
+            == RunSetup {
+                mode: GameMode::Endless,
+                multiplayer: Multiplayer::Single,
+            }
+    }
+
+    fn toggle_screen(&mut self, screen: Screen) {
+        self.screen = if self.screen == screen {
+            Screen::Game
+        } else {
+            screen
+        };
+    }
+
+    fn toggle_connection(&mut self) {
+        if self.net.take().is_some() {
+            info!("Disconnected from the relay");
+            self.model.ghosts.clear();
+            return;
+        }
+        let address = &self.model.config.net.address;
+        match NetClient::connect(address) {
+            Ok(client) => {
+                info!("Connected to the relay at {address}");
+                self.net = Some(client);
+            }
+            Err(err) => error!("Failed to connect to the relay at {address}: {err}"),
+        }
+    }
+
+    /// Starts a race for everyone connected.
+    fn start_race(&mut self) {
+        let net = match &mut self.net {
+            Some(net) => net,
+            None => return,
+        };
+        let seed = global_rng().gen();
+        if let Err(err) = net.start_race(seed) {
+            error!("Failed to start the race: {err}");
+            return;
+        }
+        self.start_run(RunSetup {
+            mode: GameMode::Race { seed },
+            ..self.model.setup
+        });
+    }
+
+    fn update_net(&mut self, delta_time: Time) {
+        let net = match &mut self.net {
+            Some(net) => net,
+            None => return,
+        };
+        let result = net.receive().and_then(|start| {
+            net.update(&self.model, delta_time.as_f32())?;
+            Ok(start)
+        });
+        match result {
+            Ok(start) => {
+                self.model.ghosts = net.ghosts();
+                if let Some(seed) = start {
+                    self.screen = Screen::Game;
+                    self.start_run(RunSetup {
+                        mode: GameMode::Race { seed },
+                        ..self.model.setup
+                    });
+                }
+            }
+            Err(err) => {
+                error!("Lost the connection to the relay: {err}");
+                self.net = None;
+                self.model.ghosts.clear();
+            }
+        }
+    }
+
+    /// The modes listed in the mode menu.
+    fn menu_modes() -> [GameMode; 6] {
+        [
+            GameMode::Endless,
+            GameMode::Daily(Date::today()),
+            GameMode::TimeAttack,
+            GameMode::Survival,
+            GameMode::NoPickups,
+            GameMode::Practice,
+        ]
+    }
+
+    /// Replaces the current run with the one from the snapshot file.
+    fn load_snapshot(&mut self) {
+        let model = match Model::import_snapshot(self.assets.achievements.list.clone()) {
+            Some(model) => model,
+            None => return,
+        };
+        if !self.statistics.is_over() {
+            self.record_run();
+            self.save_replay();
+        }
+        self.model.save_records();
+        self.model = model;
+        self.screen = Screen::Game;
+        self.playtest_height = None;
+        self.editor = None;
+        self.render = Render::new(&self.geng, &self.assets);
+        self.statistics = RunStatistics::new();
+        self.replay = ReplayRecorder::new();
+        self.resumed = true;
+        self.checkpoints = Checkpoints::new();
+    }
+
+    /// Saves the run to resume it on the next launch.
+    /// Finished runs, races and play-tests are not resumed.
+    fn save_run(&self) {
+        let resumable = !self.statistics.is_over()
+            && self.playtest_height.is_none()
+            && !matches!(self.model.setup.mode, GameMode::Race { .. });
+        if resumable {
+            self.model.save_run();
+        } else {
+            Model::clear_saved_run();
+        }
+    }
+
+    /// Rewinds the practice run to an earlier checkpoint.
+    fn rewind(&mut self) {
+        if self.model.setup.mode != GameMode::Practice {
+            return;
+        }
+        let rewind_time = self.model.config.practice.rewind_time;
+        if let Some(mut model) = self.checkpoints.rewind(rewind_time) {
+            // The settings are kept as they are now
+            model.pop_timer = self.model.pop_timer;
+            model.ghosts = std::mem::take(&mut self.model.ghosts);
+            self.model = model;
+        }
+    }
+
+    /// Opens the editor on the selected level.
+    fn open_editor(&mut self) {
+        let index = self.selected_level;
+        let level = match self.levels.get(index) {
+            Some(level) => level.clone(),
+            None => Editor::new_level(index),
+        };
+        self.editor = Some(Editor::new(
+            index,
+            level,
+            self.assets.config.camera.fov.as_f32(),
+        ));
+        self.playtest_height = None;
+        self.screen = Screen::Editor;
+    }
+
+    fn handle_editor_action(&mut self, action: EditorAction) {
+        let editor = match &self.editor {
+            Some(editor) => editor,
+            None => return,
+        };
+        match action {
+            EditorAction::Exit => {
+                self.editor = None;
+                self.screen = Screen::Levels;
+            }
+            EditorAction::Save => {
+                let level = editor.level.clone();
+                match self.levels.get_mut(editor.index) {
+                    Some(saved) => *saved = level,
+                    None => self.levels.push(level),
+                }
+                editor::save_levels(&self.levels);
+            }
+            EditorAction::Playtest(height) => {
+                let index = editor.index;
+                self.playtest_height = Some(height);
+                self.screen = Screen::Game;
+                self.restart(RunSetup {
+                    mode: GameMode::Level(index),
+                    multiplayer: Multiplayer::Single,
+                });
+            }
+        }
+    }
+
+    /// Returns from play-testing back to the editor.
+    fn stop_playtest(&mut self) {
+        self.playtest_height = None;
+        self.screen = Screen::Editor;
+        // The edited level might not be in the list
+        self.restart(RunSetup {
+            mode: GameMode::Endless,
+            ..self.model.setup
+        });
+    }
+
+    /// Starts a new run, leaving the play-test if there is one.
+    fn start_run(&mut self, setup: RunSetup) {
+        self.playtest_height = None;
+        self.editor = None;
+        self.restart(setup);
     }
 
     fn reset(&mut self) {
-        self.model.reset();
+        self.restart(self.model.setup);
+    }
+
+    fn restart(&mut self, setup: RunSetup) {
+        if !self.statistics.is_over() {
+            // Abandoned runs count too
+            self.record_run();
+            self.save_replay();
+        }
+        let level = match setup.mode {
+            GameMode::Level(index) => match (&self.editor, self.playtest_height) {
+                (Some(editor), Some(height)) => Some((index, editor.level.clone(), height, false)),
+                _ => match self.levels.get(index) {
+                    Some(level) => Some((index, level.clone(), Coord::ZERO, true)),
+                    // A level from a snapshot that is not in the local list
+                    None => self
+                        .model
+                        .level
+                        .as_ref()
+                        .map(|level| (index, level.level.clone(), Coord::ZERO, false)),
+                },
+            },
+            _ => None,
+        };
+        let mut setup = setup;
+        if level.is_none() && matches!(setup.mode, GameMode::Level(_)) {
+            setup.mode = GameMode::Endless;
+        }
+        self.model.restart(setup);
+        if let Some((index, level, height, record)) = level {
+            self.model.start_level(index, level, height, record);
+        }
         self.render = Render::new(&self.geng, &self.assets);
+        self.statistics = RunStatistics::new();
+        self.replay = ReplayRecorder::new();
+        self.resumed = false;
+        self.checkpoints = Checkpoints::new();
+    }
+}
+
+impl Drop for Game {
+    fn drop(&mut self) {
+        // The window is closing
+        self.model.save_records();
+        self.save_run();
     }
 }
 
 impl geng::State for Game {
     fn draw(&mut self, framebuffer: &mut ugli::Framebuffer) {
         ugli::clear(framebuffer, Some(Rgba::from_rgb(0.0, 0.7, 0.7)), None, None);
-        self.render.draw(&self.model, framebuffer);
+        if let (Screen::Editor, Some(editor)) = (self.screen, &mut self.editor) {
+            editor.framebuffer_size = framebuffer.size().map(|x| x as f32);
+            self.render.draw_editor(editor, framebuffer);
+            return;
+        }
+        self.render.draw(&self.model, &self.statistics, framebuffer);
+        match self.screen {
+            Screen::Game | Screen::Editor => {}
+            Screen::Achievements => self.render.draw_achievements(&self.model, framebuffer),
+            Screen::Statistics => self
+                .render
+                .draw_statistics(&self.lifetime_statistics, framebuffer),
+            Screen::Modes => {
+                self.render
+                    .draw_modes(&Self::menu_modes(), self.selected_mode, framebuffer)
+            }
+            Screen::Levels => self.render.draw_levels(
+                &self.levels,
+                &self.level_records,
+                self.selected_level,
+                framebuffer,
+            ),
+        }
     }
 
     fn handle_event(&mut self, event: geng::Event) {
-        if let geng::Event::KeyDown { key: geng::Key::R } = event {
-            self.reset();
+        if self.screen == Screen::Editor {
+            let action = self
+                .editor
+                .as_mut()
+                .and_then(|editor| editor.handle_event(event));
+            if let Some(action) = action {
+                self.handle_editor_action(action);
+            }
+            return;
+        }
+        if let geng::Event::KeyDown { key } = event {
+            match key {
+                geng::Key::Escape if self.playtest_height.is_some() => self.stop_playtest(),
+                geng::Key::R => self.reset(),
+                geng::Key::C => {
+                    let current = Self::menu_modes()
+                        .iter()
+                        .position(|&mode| mode == self.model.setup.mode);
+                    self.selected_mode = current.unwrap_or(0);
+                    self.toggle_screen(Screen::Modes);
+                }
+                geng::Key::Up if self.screen == Screen::Modes => {
+                    self.selected_mode = self.selected_mode.saturating_sub(1);
+                }
+                geng::Key::Down if self.screen == Screen::Modes => {
+                    let last = Self::menu_modes().len() - 1;
+                    self.selected_mode = (self.selected_mode + 1).min(last);
+                }
+                geng::Key::Enter if self.screen == Screen::Modes => {
+                    self.screen = Screen::Game;
+                    self.start_run(RunSetup {
+                        mode: Self::menu_modes()[self.selected_mode],
+                        ..self.model.setup
+                    });
+                }
+                geng::Key::P => {
+                    let multiplayer = match self.model.setup.multiplayer {
+                        Multiplayer::Single => Multiplayer::Versus,
+                        Multiplayer::Versus => Multiplayer::Coop,
+                        Multiplayer::Coop => Multiplayer::Single,
+                    };
+                    self.start_run(RunSetup {
+                        multiplayer,
+                        ..self.model.setup
+                    });
+                }
+                geng::Key::Backspace => self.rewind(),
+                geng::Key::O if self.model.setup.mode == GameMode::Practice => {
+                    self.model.pop_timer = !self.model.pop_timer;
+                }
+                geng::Key::F5 => self.model.export_snapshot(),
+                geng::Key::F9 => self.load_snapshot(),
+                geng::Key::G => self.show_best_run = !self.show_best_run,
+                geng::Key::L => {
+                    // The results could have changed since the last time
+                    self.level_records = LevelRecords::load();
+                    self.toggle_screen(Screen::Levels);
+                }
+                geng::Key::Up if self.screen == Screen::Levels => {
+                    self.selected_level = self.selected_level.saturating_sub(1);
+                }
+                geng::Key::Down if self.screen == Screen::Levels => {
+                    self.selected_level = (self.selected_level + 1).min(self.levels.len());
+                }
+                geng::Key::Enter if self.screen == Screen::Levels => {
+                    if self.selected_level < self.levels.len() {
+                        self.screen = Screen::Game;
+                        self.start_run(RunSetup {
+                            mode: GameMode::Level(self.selected_level),
+                            ..self.model.setup
+                        });
+                    } else {
+                        self.open_editor();
+                    }
+                }
+                geng::Key::E if self.screen == Screen::Levels => self.open_editor(),
+                geng::Key::N => self.toggle_connection(),
+                geng::Key::Enter => self.start_race(),
+                geng::Key::M => self.audio.music.muted = !self.audio.music.muted,
+                geng::Key::Tab => self.toggle_screen(Screen::Achievements),
+                geng::Key::I => self.toggle_screen(Screen::Statistics),
+                geng::Key::E if self.screen == Screen::Statistics => {
+                    self.lifetime_statistics.export();
+                }
+                _ => {}
+            }
         }
     }
 
     fn update(&mut self, delta_time: f64) {
+        self.update_net(Time::new(delta_time as _));
+        if self.screen != Screen::Game {
+            // The game is paused while an overlay is open
+            return;
+        }
         let delta_time = Time::new(delta_time as _);
         self.control(delta_time);
         self.model.update(delta_time);
+        let was_over = self.statistics.is_over();
+        for event in self.model.take_events() {
+            self.audio.handle_event(&self.model, &event);
+            self.render.handle_event(&event);
+            self.statistics.handle_event(&event);
+        }
+        self.statistics.update(&self.model, delta_time.as_f32());
+        self.replay.update(&self.model, delta_time.as_f32());
+        if self.model.setup.mode == GameMode::Practice {
+            self.checkpoints.update(&self.model, delta_time);
+        }
+        self.model.best_run_ghost = match &self.best_replay {
+            Some(replay) if self.show_best_run && self.is_best_run_mode() => {
+                replay.ghost_at(self.replay.time)
+            }
+            _ => None,
+        };
+        if !was_over && self.statistics.is_over() {
+            self.record_run();
+            self.save_replay();
+        }
+        self.audio.update(&self.model, delta_time.as_f32());
         self.render.update(&self.model, delta_time.as_f32());
     }
 }
@@ -4,130 +4,186 @@ use model::*;
 
 pub struct Logic<'a> {
     delta_time: Time,
+    /// The frame time not affected by the slow motion.
+    real_delta_time: Time,
     model: &'a mut Model,
 }
 
 impl Model {
-    pub fn update(&mut self, delta_time: Time) {
+    pub fn update(&mut self, real_delta_time: Time) {
+        let delta_time = real_delta_time * self.time_scale();
         let mut logic = Logic {
             delta_time,
+            real_delta_time,
             model: self,
         };
         logic.process();
     }
-
-    pub fn sound_volume(&self, position: Vec2<Coord>) -> f64 {
-        let distance = (position - self.player.position).len().as_f32();
-        (1.0 - (distance / 10.0).sqr()).max(0.0) as f64 * self.volume
-    }
-
-    pub fn play_sound(&self, sound: &geng::Sound, position: Vec2<Coord>) {
-        let mut effect = sound.effect();
-        let volume = self.sound_volume(position);
-        effect.set_volume(volume);
-        effect.play();
-    }
 }
 
 impl Logic<'_> {
     pub fn process(&mut self) {
-        self.sounds();
+        if self.model.is_completed() {
+            return;
+        }
         self.update_score();
         self.apply_gravity();
         self.player_balloon();
         self.pop();
+        self.effects();
         self.collisions();
+        self.steals();
+        self.near_misses();
+        self.bonuses();
         self.movement();
+        self.goals();
+        self.level_progress();
         self.generation();
+        self.achievements();
         self.animations();
     }
 
-    fn sounds(&mut self) {
-        // Wind
-        let volume = (self.model.player.position.y.max(Coord::ZERO) / r32(20.0))
-            .sqrt()
-            .as_f32()
-            .clamp(0.0, 1.0) as f64
-            * self.model.volume;
-        self.model.wind_sound.set_volume(volume);
-
-        // Helicopter
-        let volume = self
-            .model
-            .obstacles
-            .iter()
-            .filter(|obstacle| {
-                matches!(
-                    obstacle.obstacle_type,
-                    ObstacleType::Helicopter1 | ObstacleType::Helicopter2
-                )
-            })
-            .map(|helicopter| r64(self.model.sound_volume(helicopter.position)))
-            .max()
-            .unwrap_or(R64::ZERO)
-            .as_f32()
-            .into();
-        self.model.helicopter_sound.set_volume(volume);
-    }
-
     fn update_score(&mut self) {
-        let score = self
-            .model
-            .player
-            .position
-            .y
-            .floor()
-            .max(Coord::ZERO)
-            .as_f32() as Score;
-        self.model.score = self.model.score.max(score);
+        let milestone = self.model.config.score_milestone;
+        let best_height = |players: &[Player]| {
+            players
+                .iter()
+                .map(|player| player.height_score)
+                .max()
+                .unwrap_or(0)
+        };
+        let previous_best = best_height(&self.model.players);
+        for player in &mut self.model.players {
+            let score = player.position.y.floor().max(Coord::ZERO).as_f32() as Score;
+            player.height_score = player.height_score.max(score);
+            player.score = player.height_score + player.bonus_score;
+        }
+        let best = best_height(&self.model.players);
+        if milestone > 0 && best / milestone > previous_best / milestone {
+            self.model.events.push(Event::ScoreMilestone {
+                score: best / milestone * milestone,
+            });
+        }
+
+        let scores = self.model.players.iter().map(|player| player.score);
+        self.model.score = match self.model.setup.multiplayer {
+            Multiplayer::Single | Multiplayer::Versus => scores.max().unwrap_or(0),
+            Multiplayer::Coop => scores.sum(),
+        };
+        if self.model.setup.mode == GameMode::Survival {
+            self.model.score = self.model.time.as_f32() as Score;
+        }
     }
 
     fn pop(&mut self) {
-        self.model.next_pop -= self.delta_time;
-        if self.model.next_pop < Time::ZERO {
-            // Pop a balloon
-            let mut rng = global_rng();
-            if let Some(i) = (0..self.model.player.balloons.len()).choose(&mut rng) {
-                let balloon = self.model.player.balloons.remove(i);
-                let balloon = self
-                    .model
-                    .balloons
-                    .remove(&balloon)
-                    .expect("Popped an non-existing balloon");
-                self.model
-                    .play_sound(&self.model.assets.sounds.pop, balloon.position);
+        let resting_pauses = !self.model.config.platforms.pop_timer_continues;
+        for index in 0..self.model.players.len() {
+            let player = &self.model.players[index];
+            let frozen = !self.model.pop_timer || player.is_effect_active(PowerUpType::PopFreeze);
+            let resting = player.standing_on == Some(Surface::Platform);
+            if !frozen && !(resting && resting_pauses) {
+                self.model.players[index].next_pop -= self.delta_time;
+            }
+            self.select_pop_target(index);
+            let player = &mut self.model.players[index];
+            if player.next_pop < Time::ZERO {
+                // Pop a balloon
+                if let Some(target) = player.next_pop_target.take() {
+                    player.balloons.retain(|&id| id != target);
+                    let balloon = self
+                        .model
+                        .balloons
+                        .remove(&target)
+                        .expect("Popped an non-existing balloon");
+                    self.model.events.push(Event::BalloonPopped {
+                        id: balloon.id,
+                        position: balloon.position,
+                        color: balloon.color,
+                        cause: PopCause::Timer,
+                    });
+                }
+                player.next_pop = self.model.config.balloon_pop_time;
+            }
+        }
+    }
+
+    /// Selects the balloon that is going to pop next according to the pop policy.
+    fn select_pop_target(&mut self, player: usize) {
+        let player = &mut self.model.players[player];
+        let balloons = &self.model.balloons;
+        let height = |id: &&Id| balloons.get(*id).map(|balloon| balloon.position.y);
+        player.next_pop_target = match self.model.config.pop_policy {
+            PopPolicy::Random => match player.next_pop_target {
+                // Keep the target for the whole cycle, so the warning is consistent
+                Some(target) if player.balloons.contains(&target) => Some(target),
+                _ => player.balloons.choose(&mut self.model.rng.pop).copied(),
+            },
+            PopPolicy::Oldest => player.balloons.first().copied(),
+            PopPolicy::Lowest => player.balloons.iter().min_by_key(height).copied(),
+            PopPolicy::Highest => player.balloons.iter().max_by_key(height).copied(),
+        };
+    }
+
+    fn effects(&mut self) {
+        // The durations are in real time, slow motion does not stretch them
+        for player in &mut self.model.players {
+            for effect in &mut player.effects {
+                effect.time_left -= self.real_delta_time;
+            }
+            player
+                .effects
+                .retain(|effect| effect.time_left > Time::ZERO);
+        }
+
+        // Balloon magnet, pulls toward the closest player that has it
+        let config = &self.model.config.power_ups;
+        for balloon in &mut self.model.balloons {
+            if balloon.attached_to.is_some() {
+                continue;
+            }
+            let closest = self
+                .model
+                .players
+                .iter()
+                .filter(|player| player.alive && player.is_effect_active(PowerUpType::Magnet))
+                .map(|player| player.position - balloon.position)
+                .min_by_key(|delta| delta.len());
+            if let Some(delta) = closest {
+                if delta.len() < config.magnet_radius {
+                    balloon.velocity +=
+                        delta.normalize_or_zero() * config.magnet_acceleration * self.delta_time;
+                }
             }
-            self.model.next_pop = self.model.config.balloon_pop_time;
         }
     }
 
     fn apply_gravity(&mut self) {
-        self.model.player.velocity += self.model.config.gravity * self.delta_time;
+        for player in &mut self.model.players {
+            player.velocity += self.model.config.gravity * self.delta_time;
+        }
         for balloon in &mut self.model.balloons {
-            balloon.velocity -= self.model.config.gravity * self.delta_time;
+            balloon.velocity -= self.model.config.gravity * balloon.lift * self.delta_time;
         }
     }
 
     fn movement(&mut self) {
-        {
-            let player = &mut self.model.player;
+        for player in &mut self.model.players {
             if self.model.spawn_animation.is_some() {
-                self.model.player_control_velocity = Vec2::ZERO;
+                player.control_velocity = Vec2::ZERO;
                 player.velocity = Vec2::ZERO;
             } else {
                 player.velocity *=
                     Coord::ONE - player.velocity.len_sqr() * player.drag * self.delta_time;
-                player.position +=
-                    (player.velocity + self.model.player_control_velocity) * self.delta_time;
+                player.position += (player.velocity + player.control_velocity) * self.delta_time;
                 player.position.x = player.position.x.clamp_abs(self.model.config.arena_width);
             }
         }
         for balloon in &mut self.model.balloons {
-            if balloon.attached_to_player && self.model.spawn_animation.is_some() {
+            if balloon.attached_to.is_some() && self.model.spawn_animation.is_some() {
                 continue;
             }
 
-            balloon.drag = if balloon.attached_to_player {
+            balloon.drag = if balloon.attached_to.is_some() {
                 self.model.config.balloon_attached_drag
             } else {
                 self.model.config.balloon_drag
@@ -135,17 +191,78 @@ impl Logic<'_> {
             balloon.velocity *=
                 Coord::ONE - balloon.velocity.len_sqr() * balloon.drag * self.delta_time;
             balloon.position += balloon.velocity * self.delta_time;
-            if balloon.attached_to_player {
-                let delta = balloon.position - self.model.player.position;
-                balloon.position = self.model.player.position + delta.clamp_len(..=balloon.length);
+            balloon.steal_cooldown -= self.delta_time;
+            if let Some(player) = balloon.attached_to {
+                let player = &self.model.players[player];
+                let delta = balloon.position - player.position;
+                balloon.position = player.position + delta.clamp_len(..=balloon.length);
             }
         }
         for obstacle in &mut self.model.obstacles {
+            match &mut obstacle.movement {
+                ObstacleMovement::Linear => {}
+                ObstacleMovement::Sine {
+                    amplitude,
+                    frequency,
+                    time,
+                } => {
+                    *time += self.delta_time;
+                    let angle = r32(f32::PI * 2.0) * *frequency;
+                    let phase = (angle * *time).as_f32();
+                    obstacle.velocity.y = *amplitude * angle * r32(phase.cos());
+                }
+                ObstacleMovement::Dive {
+                    speed,
+                    trigger_distance,
+                    time_left,
+                    triggered,
+                    cruise_velocity,
+                } => {
+                    // Target the closest player
+                    let delta = self
+                        .model
+                        .players
+                        .iter()
+                        .filter(|player| player.alive)
+                        .map(|player| player.position - obstacle.position)
+                        .min_by_key(|delta| delta.len())
+                        .unwrap_or(Vec2::ZERO);
+                    if !*triggered
+                        && delta != Vec2::ZERO
+                        && delta.len() < *trigger_distance
+                        && Vec2::dot(delta, obstacle.velocity) > Coord::ZERO
+                    {
+                        *triggered = true;
+                        *cruise_velocity = obstacle.velocity;
+                    }
+                    if *triggered && *time_left > Time::ZERO {
+                        // Home toward the player
+                        *time_left -= self.delta_time;
+                        obstacle.velocity = delta.normalize_or_zero() * *speed;
+                        if *time_left <= Time::ZERO {
+                            // Fly on as before the dive
+                            obstacle.velocity = *cruise_velocity;
+                        }
+                    }
+                }
+                ObstacleMovement::Descend { speed } => {
+                    obstacle.velocity.y = -*speed;
+                }
+                ObstacleMovement::Rise { speed } => {
+                    obstacle.velocity.y = *speed;
+                }
+            }
             obstacle.position += obstacle.velocity * self.delta_time;
         }
         for cloud in &mut self.model.clouds {
             cloud.position += cloud.velocity * self.delta_time;
         }
+        for power_up in &mut self.model.power_ups {
+            power_up.position += power_up.velocity * self.delta_time;
+        }
+        for platform in &mut self.model.platforms {
+            platform.position += platform.velocity * self.delta_time;
+        }
     }
 
     fn animations(&mut self) {
@@ -163,63 +280,24 @@ impl Logic<'_> {
             }
         }
 
-        update(&mut self.model.player.animation_time, r32(1.0));
+        for player in &mut self.model.players {
+            update(&mut player.animation_time, r32(1.0));
+        }
         for obstacle in &mut self.model.obstacles {
             update(&mut obstacle.animation_time, obstacle.animation_speed);
         }
     }
 
     fn collisions(&mut self) {
-        // Player-ground
-        let player = &mut self.model.player;
-        if player.position.y < Coord::ZERO {
-            if player.velocity.y.abs() > self.model.config.gravity.y.abs() * r32(0.2) {
-                self.model.assets.sounds.splash.play();
-            }
-            player.position.y = Coord::ZERO;
-            player.velocity = Vec2::ZERO;
-        }
-
-        if player.alive {
-            // Player-obstacles
-            let mut kill = false;
-            for obstacle in &self.model.obstacles {
-                let delta = obstacle.position - player.position;
-                let penetration = obstacle.radius + player.radius - delta.len();
-                if penetration > Coord::ZERO {
-                    // Kill the player
-                    kill = true;
-                    player.velocity += obstacle.velocity;
-                    self.model.assets.sounds.hit.play();
-                    break;
-                }
-            }
-            if kill {
-                self.kill_player();
-            } else {
-                // Player-balloon
-                for balloon in &mut self.model.balloons {
-                    if balloon.attached_to_player {
-                        continue;
-                    }
-                    let delta = balloon.position - player.position;
-                    let penetration = balloon.radius + player.radius - delta.len();
-                    if penetration > Coord::ZERO {
-                        player.balloons.push(balloon.id);
-                        balloon.attached_to_player = true;
-                        if let Some(nya) = self.model.assets.sounds.nya.choose(&mut global_rng()) {
-                            nya.play();
-                        }
-                    }
-                }
-            }
+        for index in 0..self.model.players.len() {
+            self.player_collisions(index);
         }
 
         // Balloon-balloon
         let ids: Vec<Id> = self.model.balloons.ids().copied().collect();
         for id in ids {
             let mut balloon = self.model.balloons.remove(&id).unwrap();
-            if balloon.attached_to_player && self.model.spawn_animation.is_some() {
+            if balloon.attached_to.is_some() && self.model.spawn_animation.is_some() {
                 self.model.balloons.insert(balloon);
                 continue;
             }
@@ -235,24 +313,44 @@ impl Logic<'_> {
                     other.radius,
                     other.mass,
                 );
-                if collision && (balloon.attached_to_player ^ other.attached_to_player) {
-                    if !balloon.attached_to_player {
-                        balloon.attached_to_player = true;
-                        self.model.player.balloons.push(balloon.id);
-                    } else {
-                        other.attached_to_player = true;
-                        self.model.player.balloons.push(other.id);
-                    }
-                    if let Some(nya) = self.model.assets.sounds.nya.choose(&mut global_rng()) {
-                        nya.play();
-                    }
+                if !collision {
+                    continue;
                 }
+                // A free balloon bumping into a held one gets grabbed too
+                let (grabbed, player) = match (balloon.attached_to, other.attached_to) {
+                    (None, Some(player)) => (&mut balloon, player),
+                    (Some(player), None) => (other, player),
+                    _ => continue,
+                };
+                if !self.model.players[player].alive {
+                    continue;
+                }
+                grabbed.attached_to = Some(player);
+                self.model.players[player].balloons.push(grabbed.id);
+                self.model.events.push(Event::BalloonGrabbed {
+                    id: grabbed.id,
+                    player,
+                    position: grabbed.position,
+                });
             }
             self.model.balloons.insert(balloon);
         }
 
+        // Free balloons rest on the ground, obstacles crash into it
+        let ground = &self.model.config.ground;
+        for balloon in &mut self.model.balloons {
+            let (_, height) = ground.surface_at(balloon.position.x);
+            if balloon.attached_to.is_none() && balloon.position.y - balloon.radius < height {
+                balloon.position.y = height + balloon.radius;
+                balloon.velocity.y = balloon.velocity.y.max(Coord::ZERO);
+            }
+        }
+        self.model.obstacles.retain(|obstacle| {
+            let (_, height) = ground.surface_at(obstacle.position.x);
+            obstacle.position.y - obstacle.radius > height
+        });
+
         // Balloon-obstacle
-        let mut pops = Vec::new();
         for obstacle in &self.model.obstacles {
             for balloon in &mut self.model.balloons {
                 if balloon.popped {
@@ -261,112 +359,537 @@ impl Logic<'_> {
                 let delta = obstacle.position - balloon.position;
                 let penetration = obstacle.radius + balloon.radius - delta.len();
                 if penetration > Coord::ZERO {
+                    if balloon.hits_left > 0 {
+                        // Survive the hit and get pushed out of the obstacle
+                        balloon.hits_left -= 1;
+                        let direction = -delta.normalize_or_zero();
+                        balloon.position += direction * penetration;
+                        balloon.velocity = obstacle.velocity + direction * obstacle.velocity.len();
+                        continue;
+                    }
                     // Pop the balloon
                     balloon.popped = true;
-                    pops.push(balloon.position);
+                    self.model.events.push(Event::BalloonPopped {
+                        id: balloon.id,
+                        position: balloon.position,
+                        color: balloon.color,
+                        cause: PopCause::Obstacle,
+                    });
                 }
             }
         }
         self.model.balloons.retain(|b| !b.popped);
-        for pop in pops {
-            self.model.play_sound(&self.model.assets.sounds.pop, pop);
+    }
+
+    fn player_collisions(&mut self, index: usize) {
+        let invulnerable = self.model.setup.mode == GameMode::Practice;
+
+        // Player-ground
+        let ground = &self.model.config.ground;
+        let player = &mut self.model.players[index];
+        let velocity = player.velocity;
+        player.standing_on = ground.resolve(
+            &mut player.position,
+            player.velocity + player.control_velocity,
+        );
+        // The velocity of the surface stood on
+        let mut support = Vec2::ZERO;
+
+        // Player-platforms
+        if player.standing_on.is_none() {
+            for platform in &self.model.platforms {
+                let relative = player.velocity + player.control_velocity - platform.velocity;
+                let depth = platform.position.y - player.position.y;
+                // Only land from above, within the distance fallen in a frame
+                let reach = -relative.y * self.delta_time + player.radius * r32(0.5);
+                let dx = (player.position.x - platform.position.x).abs();
+                if relative.y > Coord::ZERO
+                    || depth < Coord::ZERO
+                    || depth > reach
+                    || dx > platform.half_width + player.radius * r32(0.5)
+                {
+                    continue;
+                }
+                player.position.y = platform.position.y;
+                player.standing_on = Some(Surface::Platform);
+                support = platform.velocity;
+                break;
+            }
+        }
+
+        if let Some(surface) = player.standing_on {
+            let impact_speed = support.y - velocity.y;
+            if impact_speed > ground.landing_sound_speed {
+                self.model.events.push(Event::PlayerLanded {
+                    position: player.position,
+                    impact_speed,
+                    surface,
+                });
+            }
+            player.velocity = support;
+            if player.alive && impact_speed > ground.fatal_impact_speed && !invulnerable {
+                // Fall damage
+                self.model.events.push(Event::PlayerFell {
+                    player: index,
+                    position: player.position,
+                });
+                self.kill_player(index);
+                return;
+            }
+        }
+
+        if !player.alive {
+            return;
+        }
+
+        // Player-obstacles
+        let mut hit = None;
+        for obstacle in &self.model.obstacles {
+            let delta = obstacle.position - player.position;
+            let penetration = obstacle.radius + player.radius - delta.len();
+            if penetration > Coord::ZERO {
+                hit = Some((
+                    obstacle.id,
+                    obstacle.obstacle_type,
+                    player.position + delta.normalize_or_zero() * player.radius,
+                ));
+                break;
+            }
+        }
+        let shield = player
+            .effects
+            .iter()
+            .position(|effect| effect.power_up_type == PowerUpType::Shield);
+        if let Some((_, obstacle_type, position)) = hit {
+            self.model.events.push(Event::PlayerHit {
+                player: index,
+                obstacle: obstacle_type,
+                position,
+                shielded: shield.is_some() || invulnerable,
+            });
+        }
+        match (hit, shield) {
+            (Some((obstacle, ..)), _) if invulnerable => {
+                // Knock the obstacle away as if shielded, so it does not hit again,
+                // the shield is kept
+                self.model.obstacles.remove(&obstacle);
+            }
+            (Some((obstacle, ..)), Some(shield)) => {
+                // The shield absorbs the hit and removes the obstacle
+                self.model.players[index].effects.remove(shield);
+                self.model.obstacles.remove(&obstacle);
+            }
+            (Some((obstacle, ..)), None) => {
+                // Kill the player
+                if let Some(obstacle) = self.model.obstacles.get(&obstacle) {
+                    self.model.players[index].velocity += obstacle.velocity;
+                }
+                self.kill_player(index);
+            }
+            (None, _) => {
+                // Player-power-up
+                let mut picked = Vec::new();
+                for power_up in &self.model.power_ups {
+                    let delta = power_up.position - player.position;
+                    let penetration = power_up.radius + player.radius - delta.len();
+                    if penetration > Coord::ZERO {
+                        picked.push(power_up.id);
+                    }
+                }
+                for id in picked {
+                    if let Some(power_up) = self.model.power_ups.remove(&id) {
+                        self.activate_power_up(index, power_up.power_up_type);
+                        self.model.events.push(Event::PowerUpCollected {
+                            power_up_type: power_up.power_up_type,
+                            position: power_up.position,
+                        });
+                    }
+                }
+
+                // Player-balloon
+                let player = &mut self.model.players[index];
+                for balloon in &mut self.model.balloons {
+                    if balloon.attached_to.is_some() {
+                        continue;
+                    }
+                    let delta = balloon.position - player.position;
+                    let penetration = balloon.radius + player.radius - delta.len();
+                    if penetration > Coord::ZERO {
+                        player.balloons.push(balloon.id);
+                        balloon.attached_to = Some(index);
+                        self.model.events.push(Event::BalloonGrabbed {
+                            id: balloon.id,
+                            player: index,
+                            position: balloon.position,
+                        });
+                    }
+                }
+            }
         }
     }
 
-    fn kill_player(&mut self) {
-        let player = &mut self.model.player;
+    /// When the strings of two players' balloons cross,
+    /// the higher player steals the other one's balloon.
+    fn steals(&mut self) {
+        if self.model.players.len() < 2 || self.model.spawn_animation.is_some() {
+            return;
+        }
+        let string = |balloon: &Balloon| {
+            let player = &self.model.players[balloon.attached_to?];
+            Some((player.position, balloon.position))
+        };
+        let mut stolen = Vec::new();
+        for balloon in &self.model.balloons {
+            let (owner, segment) = match (balloon.attached_to, string(balloon)) {
+                (Some(owner), Some(segment)) => (owner, segment),
+                _ => continue,
+            };
+            for other in &self.model.balloons {
+                let thief = match other.attached_to {
+                    Some(thief) => thief,
+                    None => continue,
+                };
+                if thief == owner
+                    || balloon.steal_cooldown > Time::ZERO
+                    || stolen
+                        .iter()
+                        .any(|&(id, ..)| id == balloon.id || id == other.id)
+                {
+                    continue;
+                }
+                let thief_player = &self.model.players[thief];
+                if !thief_player.alive
+                    || thief_player.position.y <= self.model.players[owner].position.y
+                {
+                    continue;
+                }
+                if string(other).map_or(false, |other| segments_cross(segment, other)) {
+                    stolen.push((balloon.id, owner, thief));
+                }
+            }
+        }
+
+        let cooldown = self.model.config.multiplayer.steal_cooldown;
+        for (id, from, to) in stolen {
+            let balloon = match self.model.balloons.get_mut(&id) {
+                Some(balloon) => balloon,
+                None => continue,
+            };
+            balloon.attached_to = Some(to);
+            balloon.steal_cooldown = cooldown;
+            self.model.players[from]
+                .balloons
+                .retain(|&other| other != id);
+            self.model.players[to].balloons.push(id);
+            self.model.events.push(Event::BalloonStolen {
+                id,
+                from,
+                to,
+                position: balloon.position,
+            });
+        }
+    }
+
+    fn near_misses(&mut self) {
+        for obstacle in &mut self.model.obstacles {
+            let close = self.model.players.iter().find(|player| {
+                let distance = (obstacle.position - player.position).len();
+                player.alive
+                    && distance
+                        < obstacle.radius + player.radius + self.model.config.near_miss_margin
+            });
+            if let Some(player) = close {
+                obstacle.passing_close = Some(player.index);
+            } else if let Some(player) = obstacle.passing_close.take() {
+                // Passed by without hitting the player
+                if self.model.players[player].alive {
+                    self.model.events.push(Event::NearMiss {
+                        player,
+                        obstacle: obstacle.obstacle_type,
+                        position: obstacle.position,
+                    });
+                }
+            }
+        }
+    }
+
+    fn bonuses(&mut self) {
+        let config = &self.model.config.scoring;
+        for player in &mut self.model.players {
+            player.combo_time_left -= self.delta_time;
+            if player.combo_time_left <= Time::ZERO {
+                player.combo = 0;
+            }
+        }
+
+        let mut bonuses = Vec::new();
+        for event in &self.model.events {
+            match *event {
+                Event::NearMiss {
+                    player, position, ..
+                } => {
+                    bonuses.push((player, config.near_miss_bonus, position, 0));
+                }
+                Event::BalloonGrabbed {
+                    id,
+                    player: index,
+                    position,
+                } => {
+                    let player = &mut self.model.players[index];
+                    player.combo += 1;
+                    player.combo_time_left = config.combo_window;
+
+                    let mut score = config.grab_bonus;
+                    if let Some(balloon) = self.model.balloons.get(&id) {
+                        score += balloon.score_bonus;
+                    }
+                    if player.position.y >= config.mid_air_height {
+                        score += config.mid_air_bonus;
+                    }
+                    let multiplier = (R32::ONE
+                        + config.combo_step * r32((player.combo - 1) as f32))
+                    .min(config.max_combo_multiplier);
+                    let score = (r32(score as f32) * multiplier).as_f32().round() as Score;
+                    bonuses.push((index, score, position, player.combo));
+                }
+                _ => {}
+            }
+        }
+
+        for (player, score, position, combo) in bonuses {
+            if score == 0 {
+                continue;
+            }
+            self.model.players[player].bonus_score += score;
+            self.model.events.push(Event::BonusScore {
+                score,
+                position,
+                combo,
+            });
+        }
+    }
+
+    fn achievements(&mut self) {
+        if self.model.setup.mode == GameMode::Practice {
+            // Nothing is earned without the risk
+            return;
+        }
+        let players = &self.model.players;
+        let progress = &mut self.model.achievements;
+        let mut changed = false;
+
+        // Track the progress
+        if players
+            .iter()
+            .any(|player| player.alive && !player.balloons.is_empty())
+        {
+            self.model.time_without_grab += self.delta_time;
+        }
+        for event in &self.model.events {
+            match *event {
+                Event::BalloonGrabbed { .. } => self.model.time_without_grab = Time::ZERO,
+                Event::NearMiss { obstacle, .. } => {
+                    *progress.near_misses.entry(obstacle).or_default() += 1;
+                    changed = true;
+                }
+                Event::PlayerHit {
+                    obstacle,
+                    shielded: false,
+                    ..
+                } => {
+                    changed |= progress.deaths.insert(obstacle);
+                }
+                _ => {}
+            }
+        }
+
+        // Check the conditions
+        let obstacle_types = [
+            ObstacleType::Plane,
+            ObstacleType::Helicopter1,
+            ObstacleType::Helicopter2,
+        ];
+        for (index, achievement) in self.model.achievement_configs.iter().enumerate() {
+            if progress.is_unlocked(achievement) {
+                continue;
+            }
+            let completed = match achievement.condition {
+                AchievementCondition::ReachHeight(height) => {
+                    players.iter().any(|player| player.height_score >= height)
+                }
+                AchievementCondition::HoldBalloons(count) => {
+                    players.iter().any(|player| player.balloons.len() >= count)
+                }
+                AchievementCondition::SurviveWithoutGrabbing(time) => {
+                    self.model.time_without_grab >= time
+                }
+                AchievementCondition::NearMisses { obstacle, count } => {
+                    progress.near_misses.get(&obstacle).copied().unwrap_or(0) >= count
+                }
+                AchievementCondition::DieToEachObstacle => obstacle_types
+                    .iter()
+                    .all(|obstacle| progress.deaths.contains(obstacle)),
+            };
+            if completed {
+                progress.unlocked.insert(achievement.name.clone());
+                self.model.events.push(Event::AchievementUnlocked { index });
+                changed = true;
+            }
+        }
+
+        if changed {
+            progress.save();
+        }
+    }
+
+    fn kill_player(&mut self, index: usize) {
+        let player = &mut self.model.players[index];
         player.alive = false;
         for balloon in player.balloons.drain(..) {
             if let Some(balloon) = self.model.balloons.get_mut(&balloon) {
-                balloon.attached_to_player = false;
+                balloon.attached_to = None;
             }
         }
     }
 
+    fn activate_power_up(&mut self, player: usize, power_up_type: PowerUpType) {
+        let duration = self
+            .model
+            .config
+            .power_ups
+            .types
+            .iter()
+            .find(|config| config.power_up_type == power_up_type)
+            .map(|config| config.duration)
+            .unwrap_or(Time::ZERO);
+        let effects = &mut self.model.players[player].effects;
+        match effects
+            .iter_mut()
+            .find(|effect| effect.power_up_type == power_up_type)
+        {
+            Some(effect) => effect.time_left = effect.time_left.max(duration),
+            None => effects.push(ActiveEffect {
+                power_up_type,
+                time_left: duration,
+            }),
+        }
+    }
+
     fn player_balloon(&mut self) {
-        let player = &mut self.model.player;
-        let mut alive_balloons = Vec::new();
-        for balloon in &player.balloons {
-            let balloon = match self.model.balloons.get_mut(balloon) {
-                None => continue,
-                Some(b) => {
-                    alive_balloons.push(*balloon);
-                    b
+        for player in &mut self.model.players {
+            let mut alive_balloons = Vec::new();
+            for balloon in &player.balloons {
+                let balloon = match self.model.balloons.get_mut(balloon) {
+                    None => continue,
+                    Some(b) => {
+                        alive_balloons.push(*balloon);
+                        b
+                    }
+                };
+                let delta = balloon.position - player.position;
+                if delta.len() < balloon.length {
+                    // No tension
+                    continue;
                 }
-            };
-            let delta = balloon.position - player.position;
-            if delta.len() < balloon.length {
-                // No tension
-                continue;
+                let (p_vel, b_vel) = collide_impulses(
+                    player.mass,
+                    player.velocity,
+                    balloon.mass,
+                    balloon.velocity,
+                    delta,
+                    r32(0.0),
+                );
+                player.velocity = p_vel;
+                balloon.velocity = b_vel;
             }
-            let (p_vel, b_vel) = collide_impulses(
-                player.mass,
-                player.velocity,
-                balloon.mass,
-                balloon.velocity,
-                delta,
-                r32(0.0),
-            );
-            player.velocity = p_vel;
-            balloon.velocity = b_vel;
+            player.balloons = alive_balloons;
         }
-        player.balloons = alive_balloons;
     }
 
     fn generation(&mut self) {
-        let mut rng = global_rng();
+        // Incoming obstacles
+        for incoming in &mut self.model.incoming_obstacles {
+            incoming.time_left -= self.delta_time;
+        }
+        let (spawned, incoming): (Vec<_>, Vec<_>) =
+            std::mem::take(&mut self.model.incoming_obstacles)
+                .into_iter()
+                .partition(|incoming| incoming.time_left <= Time::ZERO);
+        self.model.incoming_obstacles = incoming;
+        for incoming in spawned {
+            self.model.events.push(Event::ObstacleSpawned {
+                id: incoming.obstacle.id,
+                obstacle_type: incoming.obstacle.obstacle_type,
+            });
+            self.model.obstacles.insert(incoming.obstacle);
+        }
+
+        // Obstacles that rose or descended far away from the players are removed
+        let focus = self.model.focus_player().position.y;
+        let distance = self.model.config.obstacle_movement.despawn_distance;
+        self.model
+            .obstacles
+            .retain(|obstacle| (obstacle.position.y - focus).abs() < distance);
+
+        if self.model.level.is_some() {
+            // Levels are handcrafted
+            self.level_generation();
+            return;
+        }
 
         // Obstacles
         let config = &self.model.config.obstacles;
         self.model.next_obstacle -= self.delta_time;
         if self.model.next_obstacle < Time::ZERO {
-            let ahead = self.model.player.velocity.y * config.ahead_of_player;
-            let height = self.model.player.position.y
-                + ahead
-                + rng.gen_range(-config.below_player..=config.above_player);
+            // Roll everything up front, so that the sequence stays the same
+            // regardless of whether the obstacle actually spawns
+            let rng = &mut self.model.rng.obstacles;
+            let offset = rng.gen_range(-config.below_player..=config.above_player);
+            let side = r32((rng.gen_range(0..=1) * 2 - 1) as f32);
+            let speed = rng.gen_range(config.min_speed..=config.max_speed);
+            let obstacle_type = *vec![
+                ObstacleType::Plane,
+                ObstacleType::Helicopter1,
+                ObstacleType::Helicopter2,
+            ]
+            .choose(rng)
+            .expect("Failed to select the cloud type");
+            let movement_config = &self.model.config.obstacle_movement;
+            let movement_type = movement_config
+                .patterns
+                .iter()
+                .filter(|pattern| pattern.obstacle_type == obstacle_type)
+                .collect::<Vec<_>>()
+                .choose_weighted(rng, |pattern| pattern.weight.as_f32())
+                .map(|pattern| pattern.movement)
+                .unwrap_or(MovementType::Linear);
+            let delay = rng.gen_range(config.min_delay..=config.max_delay);
+
+            let focus = self.model.focus_player();
+            let ahead = focus.velocity.y * config.ahead_of_player;
+            let height = focus.position.y + ahead + offset;
             if height > config.min_height {
-                let side = r32((rng.gen_range(0..=1) * 2 - 1) as f32);
-                let radius = r32(0.5);
-                let speed = rng.gen_range(config.min_speed..=config.max_speed);
-                let x = (config.spawn_area_width + radius) * side;
-                let (obstacle_type, animation_speed) = *vec![
-                    (ObstacleType::Plane, r32(1.0)),
-                    (ObstacleType::Helicopter1, r32(5.0)),
-                    (ObstacleType::Helicopter2, r32(5.0)),
-                ]
-                .choose(&mut rng)
-                .expect("Failed to select the cloud type");
-                let obstacle = Obstacle {
-                    id: self.model.id_gen.gen(),
-                    animation_speed,
-                    animation_time: Time::ZERO,
-                    obstacle_type,
-                    position: vec2(x, height),
-                    velocity: vec2(-side * speed, Coord::ZERO),
-                    radius,
-                };
-                self.model.obstacles.insert(obstacle);
+                self.schedule_obstacle(obstacle_type, movement_type, side, height, speed);
             }
-
-            let delay = rng.gen_range(config.min_delay..=config.max_delay);
             self.model.next_obstacle += delay;
         }
 
         // Clouds
         let config = &self.model.config.clouds;
-        self.model.next_obstacle -= self.delta_time;
-        if self.model.next_obstacle < Time::ZERO {
-            let ahead = self.model.player.velocity.y * config.ahead_of_player;
-            let height = self.model.player.position.y
-                + ahead
-                + rng.gen_range(-config.below_player..=config.above_player);
+        self.model.next_cloud -= self.delta_time;
+        if self.model.next_cloud < Time::ZERO {
+            let focus = self.model.focus_player();
+            let ahead = focus.velocity.y * config.ahead_of_player;
+            let base_height = focus.position.y + ahead;
+            let rng = &mut self.model.rng.clouds;
+            let height = base_height + rng.gen_range(-config.below_player..=config.above_player);
             if height > config.min_height {
                 let side = r32((rng.gen_range(0..=1) * 2 - 1) as f32);
                 let radius = r32(0.5);
                 let speed = rng.gen_range(config.min_speed..=config.max_speed);
                 let x = (config.spawn_area_width + radius) * side;
                 let cloud_type = *vec![CloudType::Cloud0, CloudType::Cloud1, CloudType::Cloud2]
-                    .choose(&mut rng)
+                    .choose(rng)
                     .expect("Failed to select the obstacle type");
                 let cloud = Cloud {
                     id: self.model.id_gen.gen(),
@@ -378,43 +901,250 @@ impl Logic<'_> {
                 self.model.clouds.insert(cloud);
             }
 
+            let rng = &mut self.model.rng.clouds;
             let delay = rng.gen_range(config.min_delay..=config.max_delay);
-            self.model.next_obstacle += delay;
+            self.model.next_cloud += delay;
+        }
+
+        // Platforms, the ones left far below are removed
+        let lowest =
+            self.model.focus_player().position.y - self.model.config.platforms.despawn_below;
+        self.model
+            .platforms
+            .retain(|platform| platform.position.y > lowest);
+        self.model.next_platform -= self.delta_time;
+        if self.model.next_platform < Time::ZERO {
+            let config = &self.model.config.platforms;
+            let rng = &mut self.model.rng.platforms;
+            let x = r32(rng.gen_range(-1.0..=1.0)) * config.spawn_area_width;
+            let direction = r32((rng.gen_range(0..=1) * 2 - 1) as f32);
+            let platform = config
+                .types
+                .choose_weighted(&mut *rng, |config| config.weight.as_f32());
+            let delay = rng.gen_range(config.min_delay..=config.max_delay);
+
+            let y = self.model.focus_player().position.y + config.above_player;
+            if y > config.min_height {
+                if let Ok(platform) = platform {
+                    let platform = Platform {
+                        id: self.model.id_gen.gen(),
+                        platform_type: platform.platform_type,
+                        position: vec2(x, y),
+                        velocity: vec2(direction * platform.speed, Coord::ZERO),
+                        half_width: platform.width / r32(2.0),
+                    };
+                    self.model.platforms.insert(platform);
+                }
+            }
+            self.model.next_platform += delay;
+        }
+
+        if self.model.setup.mode == GameMode::NoPickups {
+            return;
+        }
+
+        // Power-ups
+        self.model.next_power_up -= self.delta_time;
+        if self.model.next_power_up < Time::ZERO {
+            let config = &self.model.config.power_ups;
+            let rng = &mut self.model.rng.power_ups;
+            let x = r32(rng.gen_range(-1.0..=1.0)) * config.spawn_area_width;
+            let power_up = config
+                .types
+                .choose_weighted(&mut *rng, |config| config.weight.as_f32());
+            let delay = rng.gen_range(config.min_delay..=config.max_delay);
+
+            let y = self.model.focus_player().position.y + config.above_player;
+            if y > config.min_height {
+                if let Ok(power_up) = power_up {
+                    let power_up = PowerUp {
+                        id: self.model.id_gen.gen(),
+                        power_up_type: power_up.power_up_type,
+                        position: vec2(x, y),
+                        velocity: vec2(Coord::ZERO, -config.fall_speed),
+                        radius: config.radius,
+                    };
+                    self.model.power_ups.insert(power_up);
+                }
+            }
+            self.model.next_power_up += delay;
         }
 
         // Balloons
         self.model.next_balloon -= self.delta_time;
         if self.model.next_balloon < Time::ZERO {
             let config = &self.model.config.balloons;
-            let y = self.model.player.position.y - config.below_player;
+            let y = self.model.focus_player().position.y - config.below_player;
             if y > config.min_height {
+                let rng = &mut self.model.rng.balloons;
                 let x = r32(rng.gen_range(-1.0..=1.0)) * config.spawn_area_width;
-                let color = *self
+                let kind = self
                     .model
                     .config
-                    .balloon_colors
-                    .choose(&mut rng)
-                    .expect("Failed to select balloon color");
-                let balloon = Balloon {
-                    id: self.model.id_gen.gen(),
-                    mass: self.model.config.balloon_mass,
-                    position: vec2(x, y),
-                    velocity: Vec2::ZERO,
-                    radius: r32(0.25),
-                    length: self.model.config.balloon_length,
-                    drag: self.model.config.balloon_drag,
-                    color,
-                    attached_to_player: false,
-                    popped: false,
-                };
+                    .balloon_kinds
+                    .choose_weighted(&mut *rng, |config| config.weight.as_f32())
+                    .map(|config| config.kind)
+                    .unwrap_or(BalloonKind::Normal);
+                let balloon = Balloon::new(
+                    self.model.id_gen.gen(),
+                    &self.model.config,
+                    kind,
+                    vec2(x, y),
+                );
                 self.model.balloons.insert(balloon);
-                let delay = rng.gen_range(config.min_delay..=config.max_delay);
+                let delay = self
+                    .model
+                    .rng
+                    .balloons
+                    .gen_range(config.min_delay..=config.max_delay);
                 self.model.next_balloon += delay;
             }
         }
     }
 }
 
+impl Logic<'_> {
+    /// Schedules an obstacle to enter the arena from the given side (-1 or 1).
+    fn schedule_obstacle(
+        &mut self,
+        obstacle_type: ObstacleType,
+        movement_type: MovementType,
+        side: Coord,
+        height: Coord,
+        speed: Coord,
+    ) {
+        let animation_speed = match obstacle_type {
+            ObstacleType::Plane => r32(1.0),
+            ObstacleType::Helicopter1 | ObstacleType::Helicopter2 => r32(5.0),
+        };
+        let radius = r32(0.5);
+        let x = (self.model.config.obstacles.spawn_area_width + radius) * side;
+        let obstacle = Obstacle {
+            id: self.model.id_gen.gen(),
+            animation_speed,
+            animation_time: Time::ZERO,
+            obstacle_type,
+            position: vec2(x, height),
+            velocity: vec2(-side * speed, Coord::ZERO),
+            radius,
+            movement: ObstacleMovement::new(movement_type, &self.model.config.obstacle_movement),
+            passing_close: None,
+        };
+        // Schedule the spawn so that the warning lasts for the configured time
+        // before the obstacle reaches the arena
+        let time_to_arena =
+            (x.abs() - radius - self.model.config.arena_width).max(Coord::ZERO) / speed;
+        let time_left = (self.model.config.obstacle_warning_time - time_to_arena).max(Time::ZERO);
+        self.model.incoming_obstacles.push(IncomingObstacle {
+            time_left,
+            obstacle,
+        });
+    }
+
+    /// Advances the run timer and checks whether the goal of the mode is reached.
+    fn goals(&mut self) {
+        if self.model.is_over() {
+            return;
+        }
+        // Slow motion does not slow down the clock
+        self.model.time += self.real_delta_time;
+        let config = &self.model.config.modes;
+        let reached = match self.model.setup.mode {
+            GameMode::TimeAttack => self
+                .model
+                .active_players()
+                .any(|player| player.position.y >= config.time_attack_height),
+            GameMode::Survival => self.model.time >= config.survival_time,
+            _ => false,
+        };
+        if reached {
+            self.model.completed = Some(self.model.time);
+        }
+    }
+
+    /// Advances the level timer and checks whether the finish is reached.
+    /// The result is saved to the records by [`Model::save_records`].
+    fn level_progress(&mut self) {
+        if self.model.is_over() {
+            return;
+        }
+        let height = self.model.focus_player().position.y;
+        let level = match &mut self.model.level {
+            Some(level) => level,
+            None => return,
+        };
+        // Slow motion does not slow down the clock
+        level.time += self.real_delta_time;
+        if height >= level.level.finish_height {
+            level.completed = Some(LevelResult {
+                time: level.time,
+                stars: level.level.stars(level.time),
+            });
+        }
+    }
+
+    /// Spawns the level entries whose triggers have fired.
+    fn level_generation(&mut self) {
+        let height = self.model.focus_player().position.y;
+        let level = match &mut self.model.level {
+            Some(level) => level,
+            None => return,
+        };
+        let time = level.time;
+        let (obstacles, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut level.pending_obstacles)
+            .into_iter()
+            .partition(|obstacle| obstacle.trigger.is_triggered(time, height));
+        level.pending_obstacles = pending;
+        let (balloons, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut level.pending_balloons)
+            .into_iter()
+            .partition(|balloon| balloon.trigger.is_triggered(time, height));
+        level.pending_balloons = pending;
+        let (clouds, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut level.pending_clouds)
+            .into_iter()
+            .partition(|cloud| cloud.trigger.is_triggered(time, height));
+        level.pending_clouds = pending;
+
+        for obstacle in obstacles {
+            self.schedule_obstacle(
+                obstacle.obstacle_type,
+                obstacle.movement.unwrap_or(MovementType::Linear),
+                obstacle.side.sign(),
+                obstacle.height,
+                obstacle.speed,
+            );
+        }
+        for balloon in balloons {
+            let balloon = Balloon::new(
+                self.model.id_gen.gen(),
+                &self.model.config,
+                balloon.kind,
+                balloon.position,
+            );
+            self.model.balloons.insert(balloon);
+        }
+        for cloud in clouds {
+            let cloud = Cloud {
+                id: self.model.id_gen.gen(),
+                cloud_type: cloud.cloud_type,
+                position: cloud.position,
+                velocity: vec2(cloud.speed, Coord::ZERO),
+                radius: r32(0.5),
+            };
+            self.model.clouds.insert(cloud);
+        }
+    }
+}
+
+/// Whether two segments given by their end points intersect.
+fn segments_cross(a: (Vec2<Coord>, Vec2<Coord>), b: (Vec2<Coord>, Vec2<Coord>)) -> bool {
+    let side = |p: Vec2<Coord>, q: Vec2<Coord>, r: Vec2<Coord>| Vec2::skew(q - p, r - p);
+    let d1 = side(b.0, b.1, a.0);
+    let d2 = side(b.0, b.1, a.1);
+    let d3 = side(a.0, a.1, b.0);
+    let d4 = side(a.0, a.1, b.1);
+    d1 * d2 < Coord::ZERO && d3 * d4 < Coord::ZERO
+}
+
 fn collide(
     position_a: &mut Vec2<Coord>,
     velocity_a: &mut Vec2<Coord>,
@@ -1,10 +1,16 @@
 use geng::prelude::*;
 
 mod assets;
+mod audio;
+mod checkpoints;
+mod editor;
 mod game;
 mod logic;
 mod model;
+mod net;
 mod render;
+mod replay;
+mod statistics;
 
 use assets::*;
 
@@ -1,8 +1,23 @@
 use super::*;
 
+use rand_chacha::ChaCha12Rng;
+
+mod achievements;
+mod daily;
+mod event;
+mod ground;
 mod id;
+mod level;
+mod modes;
+mod snapshot;
 
+pub use achievements::*;
+pub use daily::*;
+pub use event::*;
+pub use ground::*;
 pub use id::*;
+pub use level::*;
+pub use modes::*;
 
 pub type Time = R32;
 pub type Coord = R32;
@@ -10,28 +25,118 @@ pub type Score = u64;
 
 const HIGH_SCORE_SAVE_FILE: &str = "caterpillar_save";
 
+/// The whole state of a run, serializable to save and resume it.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Model {
+    pub setup: RunSetup,
     pub config: Config,
-    pub assets: Rc<Assets>,
+    /// Taken from the assets, a loaded run could come from a build with other achievements.
+    #[serde(skip)]
+    pub achievement_configs: Vec<AchievementConfig>,
     pub id_gen: IdGenerator,
+    pub rng: SpawnRng,
     pub next_obstacle: Time,
     pub next_cloud: Time,
     pub next_balloon: Time,
-    pub next_pop: Time,
-    pub player_control_velocity: Vec2<Coord>,
-    pub player: Player,
+    pub next_power_up: Time,
+    pub next_platform: Time,
+    pub players: Vec<Player>,
     pub balloons: Collection<Balloon>,
     pub obstacles: Collection<Obstacle>,
+    /// Obstacles scheduled to spawn, used to warn the player in advance.
+    pub incoming_obstacles: Vec<IncomingObstacle>,
     pub clouds: Collection<Cloud>,
+    pub power_ups: Collection<PowerUp>,
+    /// Floating platforms to rest on.
+    pub platforms: Collection<Platform>,
+    /// Whether the held balloons pop over time, can be turned off in the practice mode.
+    pub pop_timer: bool,
+    /// The high score of the current mode.
     pub high_score: Score,
+    /// The best time of the time attack.
+    pub best_time: Option<Time>,
+    /// Whether the run can update the high score,
+    /// only the first daily attempt counts if configured so.
+    pub counts_for_record: bool,
+    /// Saved on its own, a loaded run reads the current progress instead.
+    #[serde(skip)]
+    pub achievements: AchievementProgress,
+    /// Time since any player last grabbed a balloon.
+    pub time_without_grab: Time,
+    /// The score of the run, combined from the players' scores according to the rule.
     pub score: Score,
-    pub volume: f64,
-    pub helicopter_sound: geng::SoundEffect,
-    pub wind_sound: geng::SoundEffect,
     pub spawn_animation: Option<Time>,
+    /// Time since the start of the run.
+    pub time: Time,
+    /// The time the goal of the mode was reached, which ends the run.
+    pub completed: Option<Time>,
+    /// Other caterpillars shown for comparison, they do not interact with the world.
+    #[serde(skip)]
+    pub ghosts: Vec<Ghost>,
+    /// The replay of the personal best run.
+    #[serde(skip)]
+    pub best_run_ghost: Option<Ghost>,
+    /// The level being played in the level mode.
+    pub level: Option<LevelState>,
+    /// Events emitted since they were last taken by [`Model::take_events`].
+    #[serde(skip)]
+    pub events: Vec<Event>,
+}
+
+/// Everything that is chosen before the run starts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunSetup {
+    pub mode: GameMode,
+    pub multiplayer: Multiplayer,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameMode {
+    Endless,
+    /// Everyone gets the same spawns on the same day.
+    Daily(Date),
+    /// An online race, everyone connected to the relay shares the seed.
+    Race {
+        seed: u64,
+    },
+    /// A handcrafted level from the level list, set up by [`Model::start_level`].
+    Level(usize),
+    /// Reach the goal height as fast as possible.
+    TimeAttack,
+    /// Stay in the air until the time runs out, the score is the time survived.
+    Survival,
+    /// No balloons or power-ups spawn, only the initial balloons are available.
+    NoPickups,
+    /// Obstacles do not kill, and the run can be rewound to an earlier checkpoint.
+    Practice,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Multiplayer {
+    Single,
+    /// The last caterpillar flying wins.
+    Versus,
+    /// The players' altitudes are combined into one score.
+    Coop,
 }
 
+/// Separate random generators for the spawners and the pop timer,
+/// so that each sequence does not depend on how often the others are used.
+/// The generators are the ones behind `StdRng`, which is not serializable itself.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SpawnRng {
+    pub obstacles: ChaCha12Rng,
+    pub clouds: ChaCha12Rng,
+    pub power_ups: ChaCha12Rng,
+    pub balloons: ChaCha12Rng,
+    pub pop: ChaCha12Rng,
+    pub platforms: ChaCha12Rng,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Player {
+    /// Index of the player in [`Model::players`].
+    pub index: usize,
     pub animation_time: Time,
     pub alive: bool,
     pub mass: R32,
@@ -40,23 +145,85 @@ pub struct Player {
     pub radius: Coord,
     pub drag: R32,
     pub balloons: Vec<Id>,
+    pub control_velocity: Vec2<Coord>,
+    /// The surface the player is standing on, `None` while flying.
+    pub standing_on: Option<Surface>,
+    pub next_pop: Time,
+    /// The balloon that is going to pop when the timer runs out.
+    pub next_pop_target: Option<Id>,
+    pub score: Score,
+    /// The score for the maximum height reached.
+    pub height_score: Score,
+    /// The score collected from bonuses.
+    pub bonus_score: Score,
+    /// Number of balloons grabbed in a quick succession.
+    pub combo: usize,
+    /// Time left to grab the next balloon to continue the combo.
+    pub combo_time_left: Time,
+    /// The power-ups collected by the player that are still active.
+    pub effects: Vec<ActiveEffect>,
+}
+
+/// A caterpillar of someone else, only drawn for comparison.
+#[derive(Debug, Clone)]
+pub struct Ghost {
+    pub label: String,
+    pub position: Vec2<Coord>,
+    pub alive: bool,
+    pub balloons: usize,
+    pub score: Score,
+    /// The held balloons, if known.
+    pub bundle: Vec<GhostBalloon>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct GhostBalloon {
+    pub position: Vec2<Coord>,
+    pub radius: Coord,
+    pub color: Rgba<f32>,
 }
 
-#[derive(HasId)]
+#[derive(Clone, Serialize, Deserialize, HasId)]
 pub struct Balloon {
     pub id: Id,
+    pub kind: BalloonKind,
     pub mass: R32,
+    /// Multiplier of the balloon's buoyancy relative to the gravity.
+    pub lift: R32,
     pub position: Vec2<Coord>,
     pub velocity: Vec2<Coord>,
     pub radius: Coord,
     pub length: Coord,
     pub drag: R32,
     pub color: Rgba<f32>,
-    pub attached_to_player: bool,
+    /// How many more obstacle hits the balloon can survive.
+    pub hits_left: usize,
+    pub score_bonus: Score,
+    /// Index of the player holding the balloon.
+    pub attached_to: Option<usize>,
+    /// Time until the balloon can be stolen again.
+    pub steal_cooldown: Time,
     pub popped: bool,
 }
 
-#[derive(HasId)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BalloonKind {
+    Normal,
+    HeavyDuty,
+    Helium,
+    Golden,
+    Lead,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PopPolicy {
+    Random,
+    Oldest,
+    Lowest,
+    Highest,
+}
+
+#[derive(Clone, Serialize, Deserialize, HasId)]
 pub struct Obstacle {
     pub id: Id,
     pub animation_speed: R32,
@@ -65,16 +232,105 @@ pub struct Obstacle {
     pub position: Vec2<Coord>,
     pub velocity: Vec2<Coord>,
     pub radius: Coord,
+    pub movement: ObstacleMovement,
+    /// The player the obstacle is currently passing close to.
+    pub passing_close: Option<usize>,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct IncomingObstacle {
+    pub time_left: Time,
+    pub obstacle: Obstacle,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ObstacleType {
     Plane,
     Helicopter1,
     Helicopter2,
 }
 
-#[derive(HasId)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MovementType {
+    Linear,
+    Sine,
+    Dive,
+    Descend,
+    Rise,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ObstacleMovement {
+    /// Fly in a straight horizontal line.
+    Linear,
+    /// Bob up and down around the spawn height.
+    Sine {
+        amplitude: Coord,
+        frequency: R32,
+        time: Time,
+    },
+    /// Home toward the player for a short time once close enough.
+    Dive {
+        speed: Coord,
+        trigger_distance: Coord,
+        time_left: Time,
+        triggered: bool,
+        /// The velocity before the dive, restored after it.
+        cruise_velocity: Vec2<Coord>,
+    },
+    /// Slowly descend while flying.
+    Descend { speed: Coord },
+    /// Slowly rise while flying.
+    Rise { speed: Coord },
+}
+
+#[derive(Clone, Serialize, Deserialize, HasId)]
+pub struct PowerUp {
+    pub id: Id,
+    pub power_up_type: PowerUpType,
+    pub position: Vec2<Coord>,
+    pub velocity: Vec2<Coord>,
+    pub radius: Coord,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PowerUpType {
+    /// Absorbs one obstacle hit.
+    Shield,
+    /// Pauses the pop timer.
+    PopFreeze,
+    /// Pulls free balloons toward the player.
+    Magnet,
+    /// Slows down the time.
+    SlowMotion,
+}
+
+#[derive(Clone, Serialize, Deserialize, HasId)]
+pub struct Platform {
+    pub id: Id,
+    pub platform_type: PlatformType,
+    /// The middle of the top surface.
+    pub position: Vec2<Coord>,
+    pub velocity: Vec2<Coord>,
+    pub half_width: Coord,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PlatformType {
+    /// The basket of a hot-air balloon.
+    Basket,
+    Blimp,
+    /// The top of a tall building.
+    BuildingTop,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActiveEffect {
+    pub power_up_type: PowerUpType,
+    pub time_left: Time,
+}
+
+#[derive(Clone, Serialize, Deserialize, HasId)]
 pub struct Cloud {
     pub id: Id,
     pub cloud_type: CloudType,
@@ -83,7 +339,7 @@ pub struct Cloud {
     pub radius: Coord,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum CloudType {
     Cloud0,
     Cloud1,
@@ -91,77 +347,360 @@ pub enum CloudType {
 }
 
 impl Model {
-    pub fn new(config: Config, assets: &Rc<Assets>) -> Self {
+    pub fn new(
+        config: Config,
+        achievement_configs: Vec<AchievementConfig>,
+        setup: RunSetup,
+    ) -> Self {
         let mut id_gen = IdGenerator::new();
         let mut rng = global_rng();
 
         let mut balloons = Collection::new();
-        for _ in 0..config.initial_balloons {
-            let x = rng.gen_range(-0.1..=0.1);
-            let y = rng.gen_range(-0.1..=0.1);
-            let color = *config
-                .balloon_colors
-                .choose(&mut rng)
-                .expect("Failed to select balloon color");
-            let balloon = Balloon {
-                id: id_gen.gen(),
-                mass: config.balloon_mass,
-                position: vec2(0.0 + x, 0.0 + y).map(r32),
-                velocity: Vec2::ZERO,
-                radius: r32(0.25),
-                length: config.balloon_length,
-                drag: config.balloon_drag,
-                color,
-                attached_to_player: true,
-                popped: false,
-            };
-            balloons.insert(balloon);
-        }
+        let player_count = setup.multiplayer.player_count();
+        let players = (0..player_count)
+            .map(|index| {
+                // Spread the players evenly around the spawn
+                let x = config.multiplayer.spawn_spacing
+                    * r32(index as f32 - (player_count - 1) as f32 / 2.0);
+                let position = vec2(x, Coord::ZERO);
+                let mut player_balloons = Vec::new();
+                for _ in 0..config.initial_balloons {
+                    let offset = vec2(rng.gen_range(-0.1..=0.1), rng.gen_range(-0.1..=0.1));
+                    let mut balloon = Balloon::new(
+                        id_gen.gen(),
+                        &config,
+                        BalloonKind::Normal,
+                        position + offset.map(r32),
+                    );
+                    balloon.attached_to = Some(index);
+                    player_balloons.push(balloon.id);
+                    balloons.insert(balloon);
+                }
+                Player::new(index, &config, position, player_balloons)
+            })
+            .collect();
+
+        let (seed, high_score, counts_for_record) = match setup.mode {
+            GameMode::Endless => (
+                rng.gen(),
+                batbox::preferences::load(HIGH_SCORE_SAVE_FILE).unwrap_or(Score::ZERO),
+                true,
+            ),
+            GameMode::Daily(date) => {
+                let mut records = DailyRecords::load();
+                let first_attempt = records.attempts(date) == 0;
+                *records.attempts.entry(date.to_string()).or_default() += 1;
+                records.save();
+                (
+                    date.seed(),
+                    records.best(date),
+                    first_attempt || !config.daily.one_attempt_counts,
+                )
+            }
+            GameMode::Race { seed } => (seed, Score::ZERO, false),
+            GameMode::Level(_) => (rng.gen(), Score::ZERO, false),
+            GameMode::TimeAttack => (rng.gen(), Score::ZERO, true),
+            GameMode::Survival => (rng.gen(), ModeRecords::load().survival, true),
+            GameMode::NoPickups => (rng.gen(), ModeRecords::load().no_pickups, true),
+            GameMode::Practice => (rng.gen(), Score::ZERO, false),
+        };
+        let best_time = match setup.mode {
+            GameMode::TimeAttack => ModeRecords::load().time_attack,
+            _ => None,
+        };
+        // Multiplayer scores are not comparable to the solo ones
+        let counts_for_record = counts_for_record && setup.multiplayer == Multiplayer::Single;
 
         Self {
+            setup,
             id_gen,
-            assets: assets.clone(),
+            rng: SpawnRng::new(seed),
+            achievement_configs,
             next_obstacle: Time::ZERO,
             next_cloud: Time::ZERO,
             next_balloon: Time::ZERO,
-            next_pop: config.balloon_pop_time,
-            player_control_velocity: Vec2::ZERO,
-            player: Player {
-                animation_time: Time::ZERO,
-                alive: true,
-                mass: config.player_mass,
-                position: Vec2::ZERO,
-                velocity: Vec2::ZERO,
-                radius: r32(0.3),
-                drag: config.player_drag,
-                balloons: balloons.ids().copied().collect(),
-            },
+            next_power_up: config.power_ups.min_delay,
+            next_platform: config.platforms.min_delay,
+            players,
             balloons,
             obstacles: default(),
+            incoming_obstacles: Vec::new(),
             clouds: default(),
+            power_ups: default(),
+            platforms: default(),
+            pop_timer: true,
             config,
-            high_score: batbox::preferences::load(HIGH_SCORE_SAVE_FILE).unwrap_or(Score::ZERO),
+            high_score,
+            best_time,
+            counts_for_record,
+            achievements: AchievementProgress::load(),
+            time_without_grab: Time::ZERO,
             score: Score::ZERO,
-            volume: 0.5,
-            helicopter_sound: {
-                let mut effect = assets.sounds.helicopter.effect();
-                effect.set_volume(0.0);
-                effect.play();
-                effect
-            },
-            wind_sound: {
-                let mut effect = assets.sounds.wind.effect();
-                effect.set_volume(0.0);
-                effect.play();
-                effect
-            },
             spawn_animation: Some(Time::ZERO),
+            time: Time::ZERO,
+            completed: None,
+            ghosts: Vec::new(),
+            best_run_ghost: None,
+            level: None,
+            events: Vec::new(),
+        }
+    }
+
+    /// The speed at which the game time flows,
+    /// slow motion collected by any player slows down everyone.
+    pub fn time_scale(&self) -> R32 {
+        let slow_motion = self
+            .players
+            .iter()
+            .any(|player| player.is_effect_active(PowerUpType::SlowMotion));
+        if slow_motion {
+            self.config.power_ups.slow_motion_scale
+        } else {
+            R32::ONE
         }
     }
 
-    pub fn reset(&mut self) {
-        self.high_score = self.high_score.max(self.score);
-        batbox::preferences::save(HIGH_SCORE_SAVE_FILE, &self.high_score);
-        *self = Model::new(self.config.clone(), &self.assets);
+    /// Players that are still in the game.
+    pub fn active_players(&self) -> impl Iterator<Item = &Player> {
+        self.players.iter().filter(|player| !player.is_out())
+    }
+
+    /// The player the audio and the statistics follow:
+    /// the highest one still in the game.
+    pub fn focus_player(&self) -> &Player {
+        self.active_players()
+            .max_by_key(|player| player.position.y)
+            .unwrap_or(&self.players[0])
+    }
+
+    /// Whether the run has ended: everyone is out,
+    /// or in versus only one caterpillar is left flying.
+    pub fn is_over(&self) -> bool {
+        let active = self.active_players().count();
+        match self.setup.multiplayer {
+            Multiplayer::Versus => active <= 1,
+            Multiplayer::Single | Multiplayer::Coop => active == 0,
+        }
+    }
+
+    /// Starts playing the level with the players lifted to the given height,
+    /// the entries below it are skipped.
+    pub fn start_level(&mut self, index: usize, level: Level, height: Coord, record: bool) {
+        let mut state = LevelState::new(index, level, record);
+        if height > Coord::ZERO {
+            state
+                .pending_obstacles
+                .retain(|obstacle| obstacle.height >= height);
+            state
+                .pending_balloons
+                .retain(|balloon| balloon.position.y >= height);
+            let offset = vec2(Coord::ZERO, height);
+            for player in &mut self.players {
+                player.position += offset;
+                for id in &player.balloons {
+                    if let Some(balloon) = self.balloons.get_mut(id) {
+                        balloon.position += offset;
+                    }
+                }
+            }
+        }
+        self.level = Some(state);
+    }
+
+    /// Whether the goal of the mode or the finish of the level has been reached.
+    pub fn is_completed(&self) -> bool {
+        self.completed.is_some()
+            || self
+                .level
+                .as_ref()
+                .map_or(false, |level| level.completed.is_some())
+    }
+
+    /// The winner of a versus run, once they are the last one flying.
+    pub fn winner(&self) -> Option<usize> {
+        if self.setup.multiplayer != Multiplayer::Versus {
+            return None;
+        }
+        let mut active = self.active_players();
+        match (active.next(), active.next()) {
+            (Some(player), None) => Some(player.index),
+            _ => None,
+        }
+    }
+
+    pub fn take_events(&mut self) -> Vec<Event> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Saves the results and starts a new run with the given setup.
+    pub fn restart(&mut self, setup: RunSetup) {
+        self.save_records();
+        *self = Model::new(
+            self.config.clone(),
+            std::mem::take(&mut self.achievement_configs),
+            setup,
+        );
+    }
+
+    /// Saves the results of the run to the records of its mode.
+    pub fn save_records(&mut self) {
+        if self.counts_for_record && self.score > self.high_score {
+            self.high_score = self.score;
+            match self.setup.mode {
+                GameMode::Endless => {
+                    batbox::preferences::save(HIGH_SCORE_SAVE_FILE, &self.high_score);
+                }
+                GameMode::Daily(date) => {
+                    let mut records = DailyRecords::load();
+                    records.best.insert(date.to_string(), self.high_score);
+                    records.save();
+                }
+                GameMode::Survival => {
+                    let mut records = ModeRecords::load();
+                    records.survival = self.high_score;
+                    records.save();
+                }
+                GameMode::NoPickups => {
+                    let mut records = ModeRecords::load();
+                    records.no_pickups = self.high_score;
+                    records.save();
+                }
+                GameMode::Race { .. }
+                | GameMode::Level(_)
+                | GameMode::TimeAttack
+                | GameMode::Practice => {}
+            }
+        }
+        if let (GameMode::TimeAttack, Some(time)) = (self.setup.mode, self.completed) {
+            if self.counts_for_record && self.best_time.map_or(true, |best| time < best) {
+                let mut records = ModeRecords::load();
+                records.time_attack = Some(time);
+                records.save();
+            }
+        }
+        if let Some(level) = &self.level {
+            if let (true, Some(result)) = (level.record, level.completed) {
+                let mut records = LevelRecords::load();
+                records.record(&level.level, result);
+                records.save();
+            }
+        }
+        self.achievements.save();
+    }
+}
+
+impl Multiplayer {
+    pub fn player_count(self) -> usize {
+        match self {
+            Self::Single => 1,
+            Self::Versus | Self::Coop => 2,
+        }
+    }
+}
+
+impl Player {
+    pub fn new(index: usize, config: &Config, position: Vec2<Coord>, balloons: Vec<Id>) -> Self {
+        Self {
+            index,
+            animation_time: Time::ZERO,
+            alive: true,
+            mass: config.player_mass,
+            position,
+            velocity: Vec2::ZERO,
+            radius: r32(0.3),
+            drag: config.player_drag,
+            balloons,
+            control_velocity: Vec2::ZERO,
+            standing_on: None,
+            next_pop: config.balloon_pop_time,
+            next_pop_target: None,
+            score: Score::ZERO,
+            height_score: Score::ZERO,
+            bonus_score: Score::ZERO,
+            combo: 0,
+            combo_time_left: Time::ZERO,
+            effects: Vec::new(),
+        }
+    }
+
+    pub fn is_effect_active(&self, power_up_type: PowerUpType) -> bool {
+        self.effects
+            .iter()
+            .any(|effect| effect.power_up_type == power_up_type)
+    }
+
+    /// Whether the player got hit or landed without any balloons,
+    /// a caterpillar stranded on a platform can not climb any higher either.
+    pub fn is_out(&self) -> bool {
+        !self.alive || (self.balloons.is_empty() && self.standing_on.is_some())
+    }
+}
+
+impl SpawnRng {
+    pub fn new(seed: u64) -> Self {
+        let rng = |stream: u64| ChaCha12Rng::seed_from_u64(seed.wrapping_add(stream));
+        Self {
+            obstacles: rng(0),
+            clouds: rng(1),
+            power_ups: rng(2),
+            balloons: rng(3),
+            pop: rng(4),
+            platforms: rng(5),
+        }
+    }
+}
+
+impl Balloon {
+    pub fn new(id: Id, config: &Config, kind: BalloonKind, position: Vec2<Coord>) -> Self {
+        let kind_config = config.balloon_kind(kind);
+        let color = kind_config.color.unwrap_or_else(|| {
+            *config
+                .balloon_colors
+                .choose(&mut global_rng())
+                .expect("Failed to select balloon color")
+        });
+        Self {
+            id,
+            kind,
+            mass: kind_config.mass,
+            lift: kind_config.lift,
+            position,
+            velocity: Vec2::ZERO,
+            radius: kind_config.radius,
+            length: config.balloon_length,
+            drag: config.balloon_drag,
+            color,
+            hits_left: kind_config.durability,
+            score_bonus: kind_config.score_bonus,
+            attached_to: None,
+            steal_cooldown: Time::ZERO,
+            popped: false,
+        }
+    }
+}
+
+impl ObstacleMovement {
+    pub fn new(movement_type: MovementType, config: &MovementConfig) -> Self {
+        match movement_type {
+            MovementType::Linear => Self::Linear,
+            MovementType::Sine => Self::Sine {
+                amplitude: config.sine.amplitude,
+                frequency: config.sine.frequency,
+                time: Time::ZERO,
+            },
+            MovementType::Dive => Self::Dive {
+                speed: config.dive.speed,
+                trigger_distance: config.dive.trigger_distance,
+                time_left: config.dive.duration,
+                triggered: false,
+                cruise_velocity: Vec2::ZERO,
+            },
+            MovementType::Descend => Self::Descend {
+                speed: config.descend_speed,
+            },
+            MovementType::Rise => Self::Rise {
+                speed: config.rise_speed,
+            },
+        }
     }
 }
@@ -0,0 +1,27 @@
+use super::*;
+
+const ACHIEVEMENTS_SAVE_FILE: &str = "caterpillar_achievements";
+
+/// Achievement progress persisted across runs.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AchievementProgress {
+    /// Names of the unlocked achievements.
+    pub unlocked: HashSet<String>,
+    pub near_misses: HashMap<ObstacleType, usize>,
+    pub deaths: HashSet<ObstacleType>,
+}
+
+impl AchievementProgress {
+    pub fn load() -> Self {
+        batbox::preferences::load(ACHIEVEMENTS_SAVE_FILE).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        batbox::preferences::save(ACHIEVEMENTS_SAVE_FILE, self);
+    }
+
+    pub fn is_unlocked(&self, achievement: &AchievementConfig) -> bool {
+        self.unlocked.contains(&achievement.name)
+    }
+}
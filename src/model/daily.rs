@@ -0,0 +1,79 @@
+use super::*;
+
+const DAILY_SAVE_FILE: &str = "caterpillar_daily";
+
+/// A calendar day in UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Date {
+    days_since_epoch: i64,
+}
+
+/// Daily challenge results persisted per date.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DailyRecords {
+    /// Best score for each date.
+    pub best: HashMap<String, Score>,
+    /// Number of started attempts for each date.
+    pub attempts: HashMap<String, usize>,
+}
+
+impl Date {
+    pub fn today() -> Self {
+        #[cfg(not(target_arch = "wasm32"))]
+        let seconds = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("System time is before the epoch")
+            .as_secs() as i64;
+        #[cfg(target_arch = "wasm32")]
+        let seconds = (js_sys::Date::now() / 1000.0) as i64;
+        Self {
+            days_since_epoch: seconds.div_euclid(60 * 60 * 24),
+        }
+    }
+
+    /// The seed shared by everyone playing the challenge on that day.
+    pub fn seed(&self) -> u64 {
+        (self.days_since_epoch as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+    }
+
+    /// Converts to the (year, month, day) civil date.
+    pub fn civil(&self) -> (i64, u32, u32) {
+        // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+        let z = self.days_since_epoch + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = yoe + era * 400 + i64::from(month <= 2);
+        (year, month, day)
+    }
+}
+
+impl std::fmt::Display for Date {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (year, month, day) = self.civil();
+        write!(f, "{year:04}-{month:02}-{day:02}")
+    }
+}
+
+impl DailyRecords {
+    pub fn load() -> Self {
+        batbox::preferences::load(DAILY_SAVE_FILE).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        batbox::preferences::save(DAILY_SAVE_FILE, self);
+    }
+
+    pub fn best(&self, date: Date) -> Score {
+        self.best.get(&date.to_string()).copied().unwrap_or(0)
+    }
+
+    pub fn attempts(&self, date: Date) -> usize {
+        self.attempts.get(&date.to_string()).copied().unwrap_or(0)
+    }
+}
@@ -0,0 +1,75 @@
+use super::*;
+
+/// A gameplay event emitted by the logic during an update.
+#[derive(Debug, Clone)]
+pub enum Event {
+    BalloonPopped {
+        id: Id,
+        position: Vec2<Coord>,
+        color: Rgba<f32>,
+        cause: PopCause,
+    },
+    BalloonGrabbed {
+        id: Id,
+        player: usize,
+        position: Vec2<Coord>,
+    },
+    /// A balloon changed hands when the strings crossed.
+    BalloonStolen {
+        id: Id,
+        from: usize,
+        to: usize,
+        position: Vec2<Coord>,
+    },
+    PlayerHit {
+        player: usize,
+        obstacle: ObstacleType,
+        position: Vec2<Coord>,
+        /// Whether the hit was absorbed by the shield.
+        shielded: bool,
+    },
+    PlayerLanded {
+        position: Vec2<Coord>,
+        impact_speed: Coord,
+        surface: Surface,
+    },
+    /// The player landed too hard and died.
+    PlayerFell {
+        player: usize,
+        position: Vec2<Coord>,
+    },
+    PowerUpCollected {
+        power_up_type: PowerUpType,
+        position: Vec2<Coord>,
+    },
+    ObstacleSpawned {
+        id: Id,
+        obstacle_type: ObstacleType,
+    },
+    ScoreMilestone {
+        score: Score,
+    },
+    /// An obstacle passed close to the player without hitting them.
+    NearMiss {
+        player: usize,
+        obstacle: ObstacleType,
+        position: Vec2<Coord>,
+    },
+    /// Bonus score awarded for a near-miss or a balloon grab.
+    BonusScore {
+        score: Score,
+        position: Vec2<Coord>,
+        /// Number of chained balloon grabs, 0 for bonuses outside of a combo.
+        combo: usize,
+    },
+    AchievementUnlocked {
+        /// Index of the achievement in the config.
+        index: usize,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PopCause {
+    Timer,
+    Obstacle,
+}
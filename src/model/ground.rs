@@ -0,0 +1,139 @@
+use super::*;
+
+/// How far above a surface a point still counts as resting on it.
+const RESTING_DISTANCE: f32 = 1e-3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Surface {
+    /// The roof of the start house.
+    StartRoof,
+    /// The roofs of the neighbouring buildings.
+    Rooftop,
+    /// The street below the buildings.
+    Street,
+    /// A platform floating in the air.
+    Platform,
+}
+
+impl GroundConfig {
+    /// The highest surface under the given horizontal position and its height.
+    pub fn surface_at(&self, x: Coord) -> (Surface, Coord) {
+        self.buildings
+            .iter()
+            .filter(|building| building.x_min < x && x < building.x_max)
+            .map(|building| (building.surface, building.height))
+            .max_by_key(|&(_, height)| height)
+            .unwrap_or((Surface::Street, self.street_height))
+    }
+
+    /// Pushes the point out of the buildings and above the street,
+    /// returns the surface if it was landed on from above or is rested on.
+    pub fn resolve(&self, position: &mut Vec2<Coord>, velocity: Vec2<Coord>) -> Option<Surface> {
+        let resting = |y: Coord, height: Coord| {
+            y <= height + r32(RESTING_DISTANCE) && velocity.y <= Coord::ZERO
+        };
+        let mut landed = None;
+        for building in &self.buildings {
+            if position.x <= building.x_min || position.x >= building.x_max {
+                continue;
+            }
+            if position.y >= building.height {
+                if resting(position.y, building.height) {
+                    landed = Some(building.surface);
+                }
+                continue;
+            }
+            // Push out the shortest way, the walls stop the sideways movement
+            let up = building.height - position.y;
+            let left = position.x - building.x_min;
+            let right = building.x_max - position.x;
+            if up <= left.min(right) {
+                position.y = building.height;
+                landed = Some(building.surface);
+            } else if left < right {
+                position.x = building.x_min;
+            } else {
+                position.x = building.x_max;
+            }
+        }
+        if position.y < self.street_height {
+            position.y = self.street_height;
+            landed = Some(Surface::Street);
+        } else if resting(position.y, self.street_height) {
+            landed = Some(Surface::Street);
+        }
+        landed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ground() -> GroundConfig {
+        GroundConfig {
+            street_height: r32(-3.7),
+            buildings: vec![BuildingConfig {
+                surface: Surface::StartRoof,
+                x_min: r32(-5.0),
+                x_max: r32(5.0),
+                height: r32(0.0),
+            }],
+            landing_sound_speed: r32(2.0),
+            fatal_impact_speed: r32(4.3),
+        }
+    }
+
+    #[test]
+    fn falling_into_a_roof_lands_on_it() {
+        let mut position = vec2(r32(1.0), r32(-0.1));
+        let landed = ground().resolve(&mut position, vec2(r32(0.0), r32(-2.0)));
+        assert_eq!(landed, Some(Surface::StartRoof));
+        assert_eq!(position, vec2(r32(1.0), r32(0.0)));
+    }
+
+    #[test]
+    fn resting_on_a_roof_keeps_standing() {
+        let ground = ground();
+        for y in [0.0, 0.0005, 0.001] {
+            let mut position = vec2(r32(1.0), r32(y));
+            let landed = ground.resolve(&mut position, Vec2::ZERO);
+            assert_eq!(landed, Some(Surface::StartRoof));
+            assert_eq!(position.y, r32(y));
+        }
+    }
+
+    #[test]
+    fn rising_off_a_roof_is_not_standing() {
+        let mut position = vec2(r32(1.0), r32(0.0005));
+        let landed = ground().resolve(&mut position, vec2(r32(0.0), r32(1.0)));
+        assert_eq!(landed, None);
+    }
+
+    #[test]
+    fn flying_above_a_roof_is_not_standing() {
+        let mut position = vec2(r32(1.0), r32(0.5));
+        let landed = ground().resolve(&mut position, vec2(r32(0.0), r32(-1.0)));
+        assert_eq!(landed, None);
+        assert_eq!(position.y, r32(0.5));
+    }
+
+    #[test]
+    fn walls_push_sideways() {
+        let mut position = vec2(r32(4.9), r32(-2.0));
+        let landed = ground().resolve(&mut position, vec2(r32(-1.0), r32(0.0)));
+        assert_eq!(landed, None);
+        assert_eq!(position, vec2(r32(5.0), r32(-2.0)));
+    }
+
+    #[test]
+    fn the_street_is_under_everything() {
+        let ground = ground();
+        let mut position = vec2(r32(7.0), r32(-4.0));
+        let landed = ground.resolve(&mut position, vec2(r32(0.0), r32(-3.0)));
+        assert_eq!(landed, Some(Surface::Street));
+        assert_eq!(position.y, r32(-3.7));
+        let landed = ground.resolve(&mut position, Vec2::ZERO);
+        assert_eq!(landed, Some(Surface::Street));
+    }
+}
@@ -0,0 +1,228 @@
+use super::*;
+
+const LEVEL_SAVE_FILE: &str = "caterpillar_levels";
+
+/// A handcrafted course with a finish height.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Level {
+    pub name: String,
+    /// Reaching that height completes the level.
+    pub finish_height: Coord,
+    /// Completing the level within each of the times gives an extra star.
+    pub star_times: Vec<Time>,
+    #[serde(default)]
+    pub obstacles: Vec<LevelObstacle>,
+    #[serde(default)]
+    pub balloons: Vec<LevelBalloon>,
+    #[serde(default)]
+    pub clouds: Vec<LevelCloud>,
+}
+
+/// When an entry of the level spawns.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Trigger {
+    /// Right at the start of the level.
+    Start,
+    /// After that many seconds.
+    Time(Time),
+    /// Once a player reaches the height.
+    Height(Coord),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LevelObstacle {
+    #[serde(default)]
+    pub trigger: Trigger,
+    pub obstacle_type: ObstacleType,
+    /// The side the obstacle comes from.
+    pub side: Side,
+    pub height: Coord,
+    pub speed: Coord,
+    /// Linear if not specified.
+    #[serde(default)]
+    pub movement: Option<MovementType>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LevelBalloon {
+    #[serde(default)]
+    pub trigger: Trigger,
+    pub kind: BalloonKind,
+    pub position: Vec2<Coord>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LevelCloud {
+    #[serde(default)]
+    pub trigger: Trigger,
+    pub cloud_type: CloudType,
+    pub position: Vec2<Coord>,
+    /// Horizontal speed, negative to drift to the left.
+    #[serde(default)]
+    pub speed: Coord,
+}
+
+/// Progress through the level being played.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LevelState {
+    /// Index of the level in the level list.
+    pub index: usize,
+    pub level: Level,
+    /// Time since the start of the level.
+    pub time: Time,
+    /// The entries that have not spawned yet.
+    pub pending_obstacles: Vec<LevelObstacle>,
+    pub pending_balloons: Vec<LevelBalloon>,
+    pub pending_clouds: Vec<LevelCloud>,
+    pub completed: Option<LevelResult>,
+    /// Whether the result is saved to the records, not when play-testing.
+    pub record: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct LevelResult {
+    pub time: Time,
+    pub stars: usize,
+}
+
+/// Best level results persisted by the level name.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LevelRecords {
+    pub stars: HashMap<String, usize>,
+    pub best_times: HashMap<String, Time>,
+}
+
+impl Default for Trigger {
+    fn default() -> Self {
+        Self::Start
+    }
+}
+
+impl Trigger {
+    pub fn is_triggered(&self, time: Time, height: Coord) -> bool {
+        match *self {
+            Self::Start => true,
+            Self::Time(trigger) => time >= trigger,
+            Self::Height(trigger) => height >= trigger,
+        }
+    }
+}
+
+impl Side {
+    /// The sign of the horizontal position.
+    pub fn sign(self) -> Coord {
+        match self {
+            Self::Left => -Coord::ONE,
+            Self::Right => Coord::ONE,
+        }
+    }
+}
+
+impl Level {
+    /// The number of stars for completing the level in the given time.
+    pub fn stars(&self, time: Time) -> usize {
+        1 + self
+            .star_times
+            .iter()
+            .filter(|&&limit| time <= limit)
+            .count()
+    }
+
+    pub fn max_stars(&self) -> usize {
+        1 + self.star_times.len()
+    }
+}
+
+impl LevelState {
+    pub fn new(index: usize, level: Level, record: bool) -> Self {
+        Self {
+            index,
+            pending_obstacles: level.obstacles.clone(),
+            pending_balloons: level.balloons.clone(),
+            pending_clouds: level.clouds.clone(),
+            level,
+            time: Time::ZERO,
+            completed: None,
+            record,
+        }
+    }
+}
+
+impl LevelRecords {
+    pub fn load() -> Self {
+        batbox::preferences::load(LEVEL_SAVE_FILE).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        batbox::preferences::save(LEVEL_SAVE_FILE, self);
+    }
+
+    /// Keeps the result if it is better than the previous one.
+    pub fn record(&mut self, level: &Level, result: LevelResult) {
+        let stars = self.stars.entry(level.name.clone()).or_default();
+        *stars = (*stars).max(result.stars);
+        let time = self
+            .best_times
+            .entry(level.name.clone())
+            .or_insert(result.time);
+        *time = (*time).min(result.time);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn level() -> Level {
+        Level {
+            name: "Test".to_owned(),
+            finish_height: r32(50.0),
+            star_times: vec![r32(60.0), r32(30.0)],
+            obstacles: Vec::new(),
+            balloons: Vec::new(),
+            clouds: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn faster_times_give_more_stars() {
+        let level = level();
+        assert_eq!(level.stars(r32(90.0)), 1);
+        assert_eq!(level.stars(r32(60.0)), 2);
+        assert_eq!(level.stars(r32(10.0)), 3);
+        assert_eq!(level.max_stars(), 3);
+    }
+
+    #[test]
+    fn triggers() {
+        assert!(Trigger::Start.is_triggered(Time::ZERO, Coord::ZERO));
+        assert!(!Trigger::Time(r32(5.0)).is_triggered(r32(4.0), r32(100.0)));
+        assert!(Trigger::Time(r32(5.0)).is_triggered(r32(5.0), Coord::ZERO));
+        assert!(!Trigger::Height(r32(10.0)).is_triggered(r32(100.0), r32(9.0)));
+        assert!(Trigger::Height(r32(10.0)).is_triggered(Time::ZERO, r32(10.0)));
+    }
+
+    #[test]
+    fn records_keep_the_best_results() {
+        let level = level();
+        let mut records = LevelRecords::default();
+        let result = |time: f32| LevelResult {
+            time: r32(time),
+            stars: level.stars(r32(time)),
+        };
+        records.record(&level, result(45.0));
+        records.record(&level, result(90.0));
+        assert_eq!(records.stars["Test"], 2);
+        assert_eq!(records.best_times["Test"], r32(45.0));
+        records.record(&level, result(20.0));
+        assert_eq!(records.stars["Test"], 3);
+        assert_eq!(records.best_times["Test"], r32(20.0));
+    }
+}
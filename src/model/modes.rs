@@ -0,0 +1,24 @@
+use super::*;
+
+const MODE_RECORDS_SAVE_FILE: &str = "caterpillar_modes";
+
+/// Personal bests of the goal modes.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModeRecords {
+    /// Fastest time to reach the time attack height.
+    pub time_attack: Option<Time>,
+    /// Longest survived time in whole seconds.
+    pub survival: Score,
+    pub no_pickups: Score,
+}
+
+impl ModeRecords {
+    pub fn load() -> Self {
+        batbox::preferences::load(MODE_RECORDS_SAVE_FILE).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        batbox::preferences::save(MODE_RECORDS_SAVE_FILE, self);
+    }
+}
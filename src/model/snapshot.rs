@@ -0,0 +1,74 @@
+use super::*;
+
+const RUN_SAVE_FILE: &str = "caterpillar_run";
+const SNAPSHOT_FILE: &str = "snapshot.json";
+
+impl Model {
+    /// Saves the run to be resumed on the next launch.
+    pub fn save_run(&self) {
+        batbox::preferences::save(RUN_SAVE_FILE, &Some(self));
+    }
+
+    /// Forgets the saved run, so the next launch starts a new one.
+    pub fn clear_saved_run() {
+        batbox::preferences::save(RUN_SAVE_FILE, &None::<Self>);
+    }
+
+    /// The run saved by [`Model::save_run`], if there is one.
+    pub fn load_run(achievement_configs: Vec<AchievementConfig>) -> Option<Self> {
+        let mut model = batbox::preferences::load::<Option<Self>>(RUN_SAVE_FILE).flatten()?;
+        model.achievement_configs = achievement_configs;
+        model.achievements = AchievementProgress::load();
+        Some(model)
+    }
+
+    /// Writes the whole model as JSON to the working directory to attach to a bug report,
+    /// or logs it on the web.
+    pub fn export_snapshot(&self) {
+        let json = serde_json::to_string(self).expect("Failed to serialize the model");
+        #[cfg(not(target_arch = "wasm32"))]
+        match std::fs::write(SNAPSHOT_FILE, &json) {
+            Ok(()) => info!("Exported a snapshot to {SNAPSHOT_FILE}"),
+            Err(err) => error!("Failed to export a snapshot: {err}"),
+        }
+        #[cfg(target_arch = "wasm32")]
+        info!("{SNAPSHOT_FILE}:\n{json}");
+    }
+
+    /// Reads the snapshot written by [`Model::export_snapshot`].
+    /// The loaded run does not update any records.
+    pub fn import_snapshot(achievement_configs: Vec<AchievementConfig>) -> Option<Self> {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let json = match std::fs::read_to_string(SNAPSHOT_FILE) {
+                Ok(json) => json,
+                Err(err) => {
+                    error!("Failed to read {SNAPSHOT_FILE}: {err}");
+                    return None;
+                }
+            };
+            match serde_json::from_str::<Self>(&json) {
+                Ok(mut model) => {
+                    info!("Loaded a snapshot from {SNAPSHOT_FILE}");
+                    model.counts_for_record = false;
+                    model.achievement_configs = achievement_configs;
+                    model.achievements = AchievementProgress::load();
+                    if let Some(level) = &mut model.level {
+                        level.record = false;
+                    }
+                    Some(model)
+                }
+                Err(err) => {
+                    error!("Failed to parse {SNAPSHOT_FILE}: {err}");
+                    None
+                }
+            }
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            let _ = achievement_configs;
+            error!("Loading snapshots is not supported on the web");
+            None
+        }
+    }
+}
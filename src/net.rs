@@ -0,0 +1,208 @@
+use super::*;
+
+use model::*;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+
+/// How long after starting a race the race started by someone else is compared with it.
+const START_RACE_WINDOW: f32 = 2.0;
+
+/// A message exchanged with the other racers through the relay server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum NetMessage {
+    /// Sent after connecting, so that the others share their state right away.
+    Hello,
+    /// Everyone restarts in the same seeded world.
+    StartRace {
+        seed: u64,
+    },
+    State(PlayerState),
+}
+
+/// A snapshot of a racer, used to draw them as a ghost.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerState {
+    pub position: Vec2<f32>,
+    pub velocity: Vec2<f32>,
+    pub alive: bool,
+    pub balloons: usize,
+    pub score: Score,
+}
+
+/// A message as forwarded by the relay.
+#[derive(Debug, Deserialize)]
+struct Envelope {
+    from: u64,
+    /// `None` when the sender disconnected.
+    message: Option<NetMessage>,
+}
+
+/// A connection to the relay server.
+pub struct NetClient {
+    stream: TcpStream,
+    /// Received bytes that do not form a whole line yet.
+    buffer: Vec<u8>,
+    /// Bytes waiting for the socket to accept them.
+    outgoing: Vec<u8>,
+    /// The last known state of every other racer.
+    peers: HashMap<u64, Peer>,
+    next_send: f32,
+    /// The seed of the race we started and the time since then.
+    started: Option<(u64, f32)>,
+}
+
+struct Peer {
+    state: PlayerState,
+    /// Time since the state was received.
+    age: f32,
+}
+
+impl NetClient {
+    /// Connects to the relay, which works on the native builds only.
+    pub fn connect(address: &str) -> std::io::Result<Self> {
+        let stream = TcpStream::connect(address)?;
+        stream.set_nonblocking(true)?;
+        stream.set_nodelay(true)?;
+        let mut client = Self {
+            stream,
+            buffer: Vec::new(),
+            outgoing: Vec::new(),
+            peers: HashMap::new(),
+            next_send: 0.0,
+            started: None,
+        };
+        client.send(&NetMessage::Hello)?;
+        Ok(client)
+    }
+
+    pub fn send(&mut self, message: &NetMessage) -> std::io::Result<()> {
+        let mut line = serde_json::to_string(message).expect("Failed to serialize a message");
+        line.push('\n');
+        self.outgoing.extend_from_slice(line.as_bytes());
+        self.flush()
+    }
+
+    /// Starts a race with the given seed for everyone connected.
+    pub fn start_race(&mut self, seed: u64) -> std::io::Result<()> {
+        self.started = Some((seed, 0.0));
+        self.send(&NetMessage::StartRace { seed })
+    }
+
+    /// Writes as much of the queued bytes as the socket accepts without blocking,
+    /// the rest is kept for later so that the lines are never cut.
+    fn flush(&mut self) -> std::io::Result<()> {
+        while !self.outgoing.is_empty() {
+            match self.stream.write(&self.outgoing) {
+                Ok(0) => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::WriteZero,
+                        "The relay stopped accepting data",
+                    ))
+                }
+                Ok(written) => {
+                    self.outgoing.drain(..written);
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => break,
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the pending messages, updates the peers
+    /// and returns the seed of the race if someone has started one.
+    pub fn receive(&mut self) -> std::io::Result<Option<u64>> {
+        let mut chunk = [0; 4096];
+        loop {
+            match self.stream.read(&mut chunk) {
+                Ok(0) => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::ConnectionAborted,
+                        "The relay closed the connection",
+                    ))
+                }
+                Ok(read) => self.buffer.extend_from_slice(&chunk[..read]),
+                Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        let mut start = None;
+        let mut greeted = false;
+        while let Some(end) = self.buffer.iter().position(|&byte| byte == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            let envelope: Envelope = match serde_json::from_slice(&line) {
+                Ok(envelope) => envelope,
+                Err(err) => {
+                    warn!("Ignoring a malformed message: {err}");
+                    continue;
+                }
+            };
+            match envelope.message {
+                None => {
+                    self.peers.remove(&envelope.from);
+                }
+                Some(NetMessage::Hello) => greeted = true,
+                Some(NetMessage::StartRace { seed }) => match self.started {
+                    // Races started at about the same time,
+                    // everyone ends up with the lower seed
+                    Some((own, time)) if time < START_RACE_WINDOW && own <= seed => {}
+                    _ => start = Some(seed),
+                },
+                Some(NetMessage::State(state)) => {
+                    self.peers.insert(envelope.from, Peer { state, age: 0.0 });
+                }
+            }
+        }
+        if greeted {
+            // Let the newcomer see us without waiting for the next update
+            self.next_send = 0.0;
+        }
+        Ok(start)
+    }
+
+    /// Shares the local player's state every `send_interval` seconds.
+    pub fn update(&mut self, model: &Model, delta_time: f32) -> std::io::Result<()> {
+        for peer in self.peers.values_mut() {
+            peer.age += delta_time;
+        }
+        if let Some((_, time)) = &mut self.started {
+            *time += delta_time;
+        }
+        self.flush()?;
+        self.next_send -= delta_time;
+        if self.next_send > 0.0 {
+            return Ok(());
+        }
+        self.next_send = model.config.net.send_interval.as_f32();
+        let player = &model.players[0];
+        self.send(&NetMessage::State(PlayerState {
+            position: player.position.map(|x| x.as_f32()),
+            velocity: (player.velocity + player.control_velocity).map(|x| x.as_f32()),
+            alive: !player.is_out(),
+            balloons: player.balloons.len(),
+            score: player.score,
+        }))
+    }
+
+    /// The other racers, extrapolated from their last known state.
+    pub fn ghosts(&self) -> Vec<Ghost> {
+        let mut peers: Vec<_> = self.peers.iter().collect();
+        peers.sort_by_key(|&(&id, _)| id);
+        peers
+            .into_iter()
+            .map(|(id, peer)| {
+                let state = &peer.state;
+                let age = if state.alive { peer.age.min(0.5) } else { 0.0 };
+                Ghost {
+                    label: format!("Racer {}", id + 1),
+                    position: (state.position + state.velocity * age).map(r32),
+                    alive: state.alive,
+                    balloons: state.balloons,
+                    score: state.score,
+                    bundle: Vec::new(),
+                }
+            })
+            .collect()
+    }
+}
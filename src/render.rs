@@ -1,45 +1,267 @@
 use super::*;
 
+mod camera;
+mod editor;
+mod layout;
+mod particles;
+mod popups;
+
+use camera::*;
 use geng::Camera2d;
+use layout::*;
 use model::*;
+use particles::*;
+use popups::*;
+use statistics::{DeathCause, LifetimeStatistics, RunStatistics};
+use std::collections::VecDeque;
 
 pub struct Render {
     geng: Geng,
     assets: Rc<Assets>,
     camera: Camera2d,
-    camera_target: Vec2<f32>,
+    /// One camera per view: a shared one or one per player in split-screen.
+    camera_controllers: Vec<CameraController>,
+    /// Render targets for the split-screen views.
+    view_textures: Vec<ugli::Texture>,
     backgrounds: Vec<usize>,
+    time: f32,
+    particles: Particles,
+    popups: ScorePopups,
+    /// The last reached score milestone and how long it is still shown for.
+    milestone: Option<(Score, f32)>,
+    /// Unlocked achievements waiting to be announced, the first one is shown.
+    toasts: VecDeque<(usize, f32)>,
 }
 
-const CAMERA_INTERPOLATION: f32 = 0.5;
-const FOV: f32 = 10.0;
-const FOV_HORIZONTAL: f32 = FOV * 16.0 / 9.0;
+/// The aspect ratio the backgrounds and the start area are drawn for.
+const ASPECT: f32 = 16.0 / 9.0;
 
 const TEXT_COLOR: Rgba<f32> = Rgba::BLACK;
+const SKY_COLOR: Rgba<f32> = Rgba {
+    r: 0.0,
+    g: 0.7,
+    b: 0.7,
+    a: 1.0,
+};
+
+/// Clouds faster than that leave wisps behind.
+const WISP_SPEED: f32 = 0.8;
+/// Average number of wisps emitted by a fast cloud per second.
+const WISP_RATE: f32 = 3.0;
+
+const MILESTONE_DURATION: f32 = 2.0;
+const TOAST_DURATION: f32 = 3.0;
 
 impl Render {
     pub fn new(geng: &Geng, assets: &Rc<Assets>) -> Self {
+        let camera_controller = CameraController::new(&assets.config.camera);
         Self {
             geng: geng.clone(),
             assets: assets.clone(),
-            camera: Camera2d {
-                center: vec2(0.0, 2.0),
-                fov: 10.0,
-                rotation: 0.0,
-            },
-            camera_target: Vec2::ZERO,
+            camera: camera_controller.camera(),
+            camera_controllers: vec![camera_controller],
+            view_textures: Vec::new(),
             backgrounds: Vec::new(),
+            time: 0.0,
+            particles: Particles::new(),
+            popups: ScorePopups::new(),
+            milestone: None,
+            toasts: VecDeque::new(),
+        }
+    }
+
+    pub fn handle_event(&mut self, event: &Event) {
+        match *event {
+            Event::BalloonPopped {
+                position, color, ..
+            } => {
+                // Rubber shreds
+                let emitter = Emitter {
+                    amount: 12,
+                    direction: 0.0,
+                    spread: f32::PI,
+                    speed: 1.0..=3.0,
+                    gravity: vec2(0.0, -5.0),
+                    lifetime: 0.4..=0.8,
+                    size: 0.03..=0.07,
+                    spin: 10.0,
+                    color,
+                    sprite: ParticleSprite::Square,
+                };
+                self.particles.emit(&emitter, position.map(|x| x.as_f32()));
+                self.shake(self.assets.config.camera.shake_pop.as_f32());
+            }
+            Event::PlayerHit { position, .. } => {
+                self.shake(self.assets.config.camera.shake_hit.as_f32());
+                let position = position.map(|x| x.as_f32());
+                // Debris
+                let emitter = Emitter {
+                    amount: 15,
+                    direction: f32::PI / 2.0,
+                    spread: f32::PI,
+                    speed: 2.0..=5.0,
+                    gravity: vec2(0.0, -10.0),
+                    lifetime: 0.5..=1.0,
+                    size: 0.04..=0.1,
+                    spin: 15.0,
+                    color: Rgba::from_rgb(0.3, 0.3, 0.3),
+                    sprite: ParticleSprite::Square,
+                };
+                self.particles.emit(&emitter, position);
+                // Stars
+                let emitter = Emitter {
+                    amount: 6,
+                    direction: f32::PI / 2.0,
+                    spread: f32::PI / 2.0,
+                    speed: 1.0..=2.0,
+                    gravity: Vec2::ZERO,
+                    lifetime: 0.8..=1.2,
+                    size: 0.1..=0.15,
+                    spin: 5.0,
+                    color: Rgba::from_rgb(1.0, 0.9, 0.2),
+                    sprite: ParticleSprite::Star,
+                };
+                self.particles.emit(&emitter, position);
+            }
+            Event::PlayerLanded {
+                position, surface, ..
+            } => {
+                // Dust in the color of the surface
+                let color = match surface {
+                    Surface::StartRoof => Rgba::from_rgb(0.98, 0.85, 0.85),
+                    Surface::Rooftop => Rgba::from_rgb(0.9, 0.9, 0.55),
+                    Surface::Street => Rgba::from_rgb(0.5, 0.5, 0.5),
+                    Surface::Platform => Rgba::from_rgb(0.6, 0.45, 0.3),
+                };
+                let emitter = Emitter {
+                    amount: 20,
+                    direction: f32::PI / 2.0,
+                    spread: f32::PI / 3.0,
+                    speed: 2.0..=4.0,
+                    gravity: vec2(0.0, -10.0),
+                    lifetime: 0.4..=0.7,
+                    size: 0.03..=0.06,
+                    spin: 0.0,
+                    color,
+                    sprite: ParticleSprite::Circle,
+                };
+                self.particles.emit(&emitter, position.map(|x| x.as_f32()));
+            }
+            Event::ScoreMilestone { score } => {
+                self.milestone = Some((score, MILESTONE_DURATION));
+            }
+            Event::AchievementUnlocked { index } => {
+                self.toasts.push_back((index, TOAST_DURATION));
+            }
+            Event::BonusScore {
+                score,
+                position,
+                combo,
+            } => {
+                self.popups
+                    .spawn(score, combo, position.map(|x| x.as_f32()));
+            }
+            Event::PlayerFell { .. } => self.shake(self.assets.config.camera.shake_hit.as_f32()),
+            Event::BalloonStolen { position, .. } => {
+                // Sparks where the strings crossed
+                let emitter = Emitter {
+                    amount: 5,
+                    direction: f32::PI / 2.0,
+                    spread: f32::PI,
+                    speed: 0.5..=1.5,
+                    gravity: Vec2::ZERO,
+                    lifetime: 0.4..=0.6,
+                    size: 0.08..=0.12,
+                    spin: 5.0,
+                    color: Rgba::from_rgb(1.0, 0.9, 0.2),
+                    sprite: ParticleSprite::Star,
+                };
+                self.particles.emit(&emitter, position.map(|x| x.as_f32()));
+            }
+            Event::BalloonGrabbed { .. }
+            | Event::PowerUpCollected { .. }
+            | Event::ObstacleSpawned { .. }
+            | Event::NearMiss { .. } => {}
         }
     }
 
     pub fn update(&mut self, model: &Model, delta_time: f32) {
-        self.camera_target.y = model.player.position.y.as_f32() + 1.3;
-        self.camera.center +=
-            (self.camera_target - self.camera.center) / CAMERA_INTERPOLATION * delta_time;
+        self.time += delta_time;
+        self.particles.update(delta_time);
+        self.popups.update(delta_time);
+        if let Some((_, time)) = &mut self.milestone {
+            *time -= delta_time;
+            if *time <= 0.0 {
+                self.milestone = None;
+            }
+        }
+        if let Some((_, time)) = self.toasts.front_mut() {
+            *time -= delta_time;
+            if *time <= 0.0 {
+                self.toasts.pop_front();
+            }
+        }
+
+        // Wisps trailing fast clouds
+        let mut rng = global_rng();
+        for cloud in &model.clouds {
+            let velocity = cloud.velocity.map(|x| x.as_f32());
+            if velocity.len() < WISP_SPEED
+                || !rng.gen_bool((WISP_RATE * delta_time).min(1.0) as f64)
+            {
+                continue;
+            }
+            let direction = (-velocity.y).atan2(-velocity.x);
+            let emitter = Emitter {
+                amount: 1,
+                direction,
+                spread: 0.2,
+                speed: 0.1..=0.3,
+                gravity: Vec2::ZERO,
+                lifetime: 1.0..=1.5,
+                size: 0.1..=0.2,
+                spin: 0.5,
+                color: Rgba::new(1.0, 1.0, 1.0, 0.5),
+                sprite: ParticleSprite::Cloud(cloud.cloud_type as usize),
+            };
+            let position = cloud.position.map(|x| x.as_f32())
+                - velocity.normalize_or_zero() * cloud.radius.as_f32();
+            self.particles.emit(&emitter, position);
+        }
+        // Cameras
+        let split = model.config.multiplayer.split_screen && model.players.len() > 1;
+        let views = if split { model.players.len() } else { 1 };
+        if self.camera_controllers.len() != views {
+            self.camera_controllers = (0..views)
+                .map(|_| CameraController::new(&self.assets.config.camera))
+                .collect();
+        }
+        let all_players: Vec<&Player> = model.players.iter().collect();
+        for (view, controller) in self.camera_controllers.iter_mut().enumerate() {
+            let followed = if split {
+                &all_players[view..=view]
+            } else {
+                &all_players[..]
+            };
+            controller.update(model, followed, delta_time);
+        }
+
+        let target_height = self
+            .camera_controllers
+            .iter()
+            .map(|controller| {
+                let camera = controller.camera();
+                camera.center.y + camera.fov
+            })
+            .fold(f32::NEG_INFINITY, f32::max);
+        self.extend_backgrounds(target_height);
+    }
 
-        let target_height = self.camera.center.y + FOV;
-        let mut current_height = FOV * (self.backgrounds.len() as f32 - 1.0);
+    /// Adds random backgrounds until they reach the height.
+    fn extend_backgrounds(&mut self, target_height: f32) {
         let mut rng = global_rng();
+        let fov = self.fov();
+        let mut current_height = fov * (self.backgrounds.len() as f32 - 1.0);
         while current_height < target_height {
             let index = match self.backgrounds.last() {
                 Some(last) => (0..self.assets.sprites.background.len())
@@ -49,38 +271,102 @@ impl Render {
             }
             .expect("Failed to select a random background");
             self.backgrounds.push(index);
-            current_height += FOV;
+            current_height += fov;
         }
     }
 
-    pub fn draw(&mut self, model: &Model, framebuffer: &mut ugli::Framebuffer) {
-        {
-            // Background
-            let mut height = -FOV;
-            for &index in &self.backgrounds {
-                let aabb = AABB::point(vec2(-FOV_HORIZONTAL / 2.0, height))
-                    .extend_positive(vec2(FOV_HORIZONTAL, FOV));
-                let quad = draw_2d::TexturedQuad::new(aabb, &self.assets.sprites.background[index]);
-                geng::Draw2d::draw_2d(&quad, &self.geng, framebuffer, &self.camera);
-                height += FOV;
+    fn shake(&mut self, amount: f32) {
+        for controller in &mut self.camera_controllers {
+            controller.shake(amount);
+        }
+    }
+
+    pub fn draw(
+        &mut self,
+        model: &Model,
+        statistics: &RunStatistics,
+        framebuffer: &mut ugli::Framebuffer,
+    ) {
+        let views = self.camera_controllers.len();
+        if views == 1 {
+            self.draw_view(model, 0, framebuffer);
+        } else {
+            // Split-screen, every view is rendered into its own texture first
+            let framebuffer_size = framebuffer.size();
+            let view_size = vec2(framebuffer_size.x / views, framebuffer_size.y);
+            if self.view_textures.len() != views
+                || self
+                    .view_textures
+                    .iter()
+                    .any(|texture| texture.size() != view_size)
+            {
+                self.view_textures = (0..views)
+                    .map(|_| ugli::Texture::new_uninitialized(self.geng.ugli(), view_size))
+                    .collect();
+            }
+            let mut textures = std::mem::take(&mut self.view_textures);
+            for (view, texture) in textures.iter_mut().enumerate() {
+                {
+                    let mut view_framebuffer = ugli::Framebuffer::new_color(
+                        self.geng.ugli(),
+                        ugli::ColorAttachment::Texture(texture),
+                    );
+                    ugli::clear(&mut view_framebuffer, Some(SKY_COLOR), None, None);
+                    self.draw_view(model, view, &mut view_framebuffer);
+                }
+                let view_size = view_size.map(|x| x as f32);
+                let aabb =
+                    AABB::point(vec2(view_size.x * view as f32, 0.0)).extend_positive(view_size);
+                let quad = draw_2d::TexturedQuad::new(aabb, &*texture);
+                geng::Draw2d::draw_2d(&quad, &self.geng, framebuffer, &geng::PixelPerfectCamera);
+
+                if view > 0 {
+                    // Divider
+                    let x = aabb.x_min;
+                    let segment = Segment::new(vec2(x, 0.0), vec2(x, view_size.y));
+                    let segment = draw_2d::Segment::new(segment, 4.0, TEXT_COLOR);
+                    geng::Draw2d::draw_2d(
+                        &segment,
+                        &self.geng,
+                        framebuffer,
+                        &geng::PixelPerfectCamera,
+                    );
+                }
             }
+            self.view_textures = textures;
         }
 
+        self.draw_ui(model, statistics, framebuffer);
+    }
+
+    /// Draws the world as seen by one of the cameras.
+    fn draw_view(&mut self, model: &Model, view: usize, framebuffer: &mut ugli::Framebuffer) {
+        let framebuffer_size = framebuffer.size().map(|x| x as f32);
+        let controller = &mut self.camera_controllers[view];
+        controller.set_aspect(framebuffer_size.x / framebuffer_size.y);
+        self.camera = controller.camera();
+
+        self.draw_background(framebuffer);
+
         // Start area
-        let start_aabb = AABB::point(vec2(0.0, -3.7))
-            .extend_symmetric(vec2(FOV_HORIZONTAL, 0.0) / 2.0)
-            .extend_up(FOV);
+        let start_aabb = AABB::point(vec2(0.0, model.config.ground.street_height.as_f32()))
+            .extend_symmetric(vec2(self.fov() * ASPECT, 0.0) / 2.0)
+            .extend_up(self.fov());
         let height = self.camera.center.y;
-        let quad = draw_2d::TexturedQuad::new(
+        self.draw_tiled(
             start_aabb.translate(vec2(0.0, height / 5.0)),
             &self.assets.sprites.start[2],
+            framebuffer,
         );
-        geng::Draw2d::draw_2d(&quad, &self.geng, framebuffer, &self.camera);
-        let quad = draw_2d::TexturedQuad::new(
+        self.draw_tiled(
             start_aabb.translate(vec2(0.0, height / 10.0)),
             &self.assets.sprites.start[1],
+            framebuffer,
         );
-        geng::Draw2d::draw_2d(&quad, &self.geng, framebuffer, &self.camera);
+
+        if let Some(level) = &model.level {
+            self.draw_finish_line(level.level.finish_height.as_f32(), framebuffer);
+        }
 
         // Clouds
         for cloud in &model.clouds {
@@ -96,31 +382,56 @@ impl Render {
             geng::Draw2d::draw_2d(&quad, &self.geng, framebuffer, &self.camera);
         }
 
+        // Platforms
+        for platform in &model.platforms {
+            let top = platform.position.map(|x| x.as_f32());
+            let half_width = platform.half_width.as_f32();
+            match platform.platform_type {
+                PlatformType::Basket => {
+                    let envelope = draw_2d::Ellipse::circle(
+                        top + vec2(0.0, 2.0),
+                        half_width * 1.5,
+                        Rgba::from_rgb(0.93, 0.39, 0.32),
+                    );
+                    geng::Draw2d::draw_2d(&envelope, &self.geng, framebuffer, &self.camera);
+                    for side in [-1.0, 1.0] {
+                        let rope = Segment::new(
+                            top + vec2(side * half_width, 0.0),
+                            top + vec2(side * half_width, 1.0),
+                        );
+                        let rope = draw_2d::Segment::new(rope, 0.03, Rgba::from_rgb(0.3, 0.2, 0.1));
+                        geng::Draw2d::draw_2d(&rope, &self.geng, framebuffer, &self.camera);
+                    }
+                    let basket = AABB::point(top)
+                        .extend_symmetric(vec2(half_width, 0.0))
+                        .extend_down(0.6);
+                    let quad = draw_2d::Quad::new(basket, Rgba::from_rgb(0.55, 0.35, 0.2));
+                    geng::Draw2d::draw_2d(&quad, &self.geng, framebuffer, &self.camera);
+                }
+                PlatformType::Blimp => {
+                    let blimp = draw_2d::Ellipse::new(
+                        top - vec2(0.0, 0.5),
+                        vec2(half_width, 0.5),
+                        Rgba::from_rgb(0.75, 0.75, 0.8),
+                    );
+                    geng::Draw2d::draw_2d(&blimp, &self.geng, framebuffer, &self.camera);
+                }
+                PlatformType::BuildingTop => {
+                    let building = AABB::point(top)
+                        .extend_symmetric(vec2(half_width, 0.0))
+                        .extend_down(self.fov());
+                    let quad = draw_2d::Quad::new(building, Rgba::from_rgb(0.61, 0.73, 0.74));
+                    geng::Draw2d::draw_2d(&quad, &self.geng, framebuffer, &self.camera);
+                }
+            }
+        }
+
         // Obstacles
         for obstacle in &model.obstacles {
             let mut aabb = AABB::point(obstacle.position)
                 .extend_uniform(obstacle.radius * r32(1.5))
                 .map(|x| x.as_f32());
-            let mut mirror = obstacle.velocity.x < Coord::ZERO;
-            let texture = match obstacle.obstacle_type {
-                ObstacleType::Plane => self
-                    .assets
-                    .sprites
-                    .airplane
-                    .get_frame(obstacle.animation_time),
-                ObstacleType::Helicopter1 => self
-                    .assets
-                    .sprites
-                    .helicopter
-                    .get_frame(obstacle.animation_time),
-                ObstacleType::Helicopter2 => {
-                    mirror = !mirror;
-                    self.assets
-                        .sprites
-                        .helicopter2
-                        .get_frame(obstacle.animation_time)
-                }
-            };
+            let (texture, mirror) = self.obstacle_sprite(obstacle);
             if mirror {
                 std::mem::swap(&mut aabb.x_min, &mut aabb.x_max);
             }
@@ -130,34 +441,127 @@ impl Render {
 
         // Balloons
         for balloon in &model.balloons {
+            // Inflate and flash the balloon that is about to pop
+            let holder = balloon.attached_to.map(|player| &model.players[player]);
+            let warning = match holder {
+                Some(player)
+                    if player.next_pop_target == Some(balloon.id)
+                        && player.next_pop < model.config.pop_warning_time =>
+                {
+                    (Time::ONE - player.next_pop / model.config.pop_warning_time).as_f32()
+                }
+                _ => 0.0,
+            };
             let aabb = AABB::point(balloon.position)
-                .extend_uniform(balloon.radius * r32(1.5))
+                .extend_uniform(balloon.radius * r32(1.5 * (1.0 + 0.4 * warning)))
                 .map(|x| x.as_f32());
+            let flash = if (self.time * (4.0 + 16.0 * warning)).sin() > 0.0 {
+                warning * 0.8
+            } else {
+                0.0
+            };
+            let color = Rgba::new(
+                balloon.color.r + (1.0 - balloon.color.r) * flash,
+                balloon.color.g + (1.0 - balloon.color.g) * flash,
+                balloon.color.b + (1.0 - balloon.color.b) * flash,
+                balloon.color.a,
+            );
 
-            if balloon.attached_to_player {
+            if let Some(player) = holder {
                 let segment = Segment::new(
                     vec2(aabb.center().x, aabb.y_min + balloon.radius.as_f32() * 0.1),
-                    model.player.position.map(|x| x.as_f32()),
+                    player.position.map(|x| x.as_f32()),
                 );
                 let segment = draw_2d::Segment::new(segment, 0.02, Rgba::BLACK);
                 geng::Draw2d::draw_2d(&segment, &self.geng, framebuffer, &self.camera);
             }
 
-            let quad =
-                draw_2d::TexturedQuad::colored(aabb, &self.assets.sprites.balloon, balloon.color);
+            let quad = draw_2d::TexturedQuad::colored(aabb, &self.assets.sprites.balloon, color);
             geng::Draw2d::draw_2d(&quad, &self.geng, framebuffer, &self.camera);
         }
 
+        // Power-ups
+        let font = &**self.geng.default_font();
+        for power_up in &model.power_ups {
+            let position = power_up.position.map(|x| x.as_f32());
+            let radius = power_up.radius.as_f32();
+            let color = power_up_color(power_up.power_up_type);
+            let circle = draw_2d::Ellipse::circle(position, radius, color);
+            geng::Draw2d::draw_2d(&circle, &self.geng, framebuffer, &self.camera);
+            let circle = draw_2d::Ellipse::circle(position, radius * 0.8, Rgba::WHITE);
+            geng::Draw2d::draw_2d(&circle, &self.geng, framebuffer, &self.camera);
+            let text = &power_up_name(power_up.power_up_type)[..1];
+            let text = draw_2d::Text::unit(font, text, color)
+                .scale_uniform(radius * 0.6)
+                .translate(position);
+            geng::Draw2d::draw_2d(&text, &self.geng, framebuffer, &self.camera);
+        }
+
         // Spawn house
         let quad = draw_2d::TexturedQuad::new(start_aabb, &self.assets.sprites.start[0]);
         geng::Draw2d::draw_2d(&quad, &self.geng, framebuffer, &self.camera);
 
-        {
-            // Player
-            let player = &model.player;
+        // Ghosts
+        for ghost in model.ghosts.iter().chain(&model.best_run_ghost) {
+            let alpha = model.config.ghosts.alpha.as_f32();
+            let position = ghost.position.map(|x| x.as_f32());
+            let radius = model.players[0].radius.as_f32();
+            for balloon in &ghost.bundle {
+                let segment = Segment::new(
+                    balloon.position.map(|x| x.as_f32()) - vec2(0.0, balloon.radius.as_f32() * 1.4),
+                    position,
+                );
+                let color = Rgba {
+                    a: alpha,
+                    ..Rgba::BLACK
+                };
+                let segment = draw_2d::Segment::new(segment, 0.02, color);
+                geng::Draw2d::draw_2d(&segment, &self.geng, framebuffer, &self.camera);
+                let aabb = AABB::point(balloon.position)
+                    .extend_uniform(balloon.radius * r32(1.5))
+                    .map(|x| x.as_f32());
+                let color = Rgba {
+                    a: balloon.color.a * alpha,
+                    ..balloon.color
+                };
+                let quad =
+                    draw_2d::TexturedQuad::colored(aabb, &self.assets.sprites.balloon, color);
+                geng::Draw2d::draw_2d(&quad, &self.geng, framebuffer, &self.camera);
+            }
+
+            let aabb = AABB::ZERO.extend_uniform(radius);
+            let mut transform = Mat3::translate(position);
+            let texture = if ghost.alive {
+                self.assets
+                    .sprites
+                    .player
+                    .get_frame(Time::new(self.time.fract()))
+            } else {
+                transform *= Mat3::rotate(-f32::PI / 4.0);
+                &self.assets.sprites.player[0]
+            };
+            let color = Rgba::new(1.0, 1.0, 1.0, alpha);
+            let quad = draw_2d::TexturedQuad::colored(aabb, texture, color).transform(transform);
+            geng::Draw2d::draw_2d(&quad, &self.geng, framebuffer, &self.camera);
+            let text = format!("{} ({})", ghost.label, ghost.balloons);
+            let text = draw_2d::Text::unit(
+                font,
+                text,
+                Rgba {
+                    a: color.a,
+                    ..TEXT_COLOR
+                },
+            )
+            .scale_uniform(radius * 0.5)
+            .translate(position + vec2(0.0, radius * 1.5));
+            geng::Draw2d::draw_2d(&text, &self.geng, framebuffer, &self.camera);
+        }
+
+        // Players
+        for player in &model.players {
             let aabb = AABB::ZERO.extend_uniform(player.radius).map(|x| x.as_f32());
             let mut transform = Mat3::translate(player.position.map(|x| x.as_f32()));
-            let texture = if player.balloons.is_empty() && player.position.y <= r32(0.1) {
+            let texture = if player.is_out() && player.standing_on.is_some() {
                 self.assets
                     .sprites
                     .player_dead
@@ -168,85 +572,849 @@ impl Render {
             } else {
                 self.assets.sprites.player.get_frame(player.animation_time)
             };
-            let quad = draw_2d::TexturedQuad::new(aabb, texture).transform(transform);
+            let quad = draw_2d::TexturedQuad::colored(aabb, texture, player_color(model, player))
+                .transform(transform);
             geng::Draw2d::draw_2d(&quad, &self.geng, framebuffer, &self.camera);
+
+            // Shield bubble
+            if player.alive && player.is_effect_active(PowerUpType::Shield) {
+                let mut color = power_up_color(PowerUpType::Shield);
+                color.a = 0.3;
+                let circle = draw_2d::Ellipse::circle(
+                    player.position.map(|x| x.as_f32()),
+                    player.radius.as_f32() * 1.5,
+                    color,
+                );
+                geng::Draw2d::draw_2d(&circle, &self.geng, framebuffer, &self.camera);
+            }
         }
 
+        // Particles
+        self.particles
+            .draw(&self.geng, &self.assets, framebuffer, &self.camera);
+
+        // Score popups
+        self.popups.draw(&self.geng, framebuffer, &self.camera);
+
         // Spawn animation
         if let Some(time) = model.spawn_animation {
             let texture = self.assets.sprites.spawn.get_frame(time);
-            let size = model.player.radius * r32(4.0);
-            let aabb = AABB::ZERO.extend_uniform(size).map(|x| x.as_f32());
+            for player in &model.players {
+                let aabb = AABB::point(player.position)
+                    .extend_uniform(player.radius * r32(4.0))
+                    .map(|x| x.as_f32());
+                let quad = draw_2d::TexturedQuad::new(aabb, texture);
+                geng::Draw2d::draw_2d(&quad, &self.geng, framebuffer, &self.camera);
+            }
+        }
+
+        if !model.is_over() {
+            let layout = Layout::new(framebuffer_size);
+            self.draw_warnings(model, &layout, framebuffer);
+        }
+    }
+
+    fn draw_background(&self, framebuffer: &mut ugli::Framebuffer) {
+        let fov = self.fov();
+        let mut height = -fov;
+        for &index in &self.backgrounds {
+            let aabb = AABB::point(vec2(-fov * ASPECT / 2.0, height))
+                .extend_positive(vec2(fov * ASPECT, fov));
+            self.draw_tiled(aabb, &self.assets.sprites.background[index], framebuffer);
+            height += fov;
+        }
+    }
+
+    /// The default field of view, the height of a background tile.
+    fn fov(&self) -> f32 {
+        self.assets.config.camera.fov.as_f32()
+    }
+
+    fn draw_finish_line(&self, height: f32, framebuffer: &mut ugli::Framebuffer) {
+        let half_width = self.fov() * ASPECT / 2.0;
+        let segment = Segment::new(vec2(-half_width, height), vec2(half_width, height));
+        let segment = draw_2d::Segment::new(segment, 0.1, Rgba::WHITE);
+        geng::Draw2d::draw_2d(&segment, &self.geng, framebuffer, &self.camera);
+        let font = &**self.geng.default_font();
+        let text = draw_2d::Text::unit(font, "Finish", TEXT_COLOR)
+            .scale_uniform(0.3)
+            .align_bounding_box(vec2(0.5, 0.0))
+            .translate(vec2(0.0, height + 0.2));
+        geng::Draw2d::draw_2d(&text, &self.geng, framebuffer, &self.camera);
+    }
+
+    /// Draws the texture repeated horizontally to fill the whole view,
+    /// every other copy is mirrored so that the edges line up.
+    fn draw_tiled(
+        &self,
+        aabb: AABB<f32>,
+        texture: &ugli::Texture,
+        framebuffer: &mut ugli::Framebuffer,
+    ) {
+        let framebuffer_size = framebuffer.size().map(|x| x as f32);
+        let view_width = self.camera.fov * framebuffer_size.x / framebuffer_size.y;
+        let width = aabb.width();
+        let extra = (self.camera.center.x - aabb.center().x).abs() + view_width / 2.0 - width / 2.0;
+        let copies = (extra / width).ceil().max(0.0) as i32;
+        for i in -copies..=copies {
+            let mut aabb = aabb.translate(vec2(width * i as f32, 0.0));
+            if i % 2 != 0 {
+                std::mem::swap(&mut aabb.x_min, &mut aabb.x_max);
+            }
             let quad = draw_2d::TexturedQuad::new(aabb, texture);
             geng::Draw2d::draw_2d(&quad, &self.geng, framebuffer, &self.camera);
         }
+    }
 
-        self.draw_ui(model, framebuffer);
+    /// Returns the current frame of the obstacle's sprite and whether it should be mirrored.
+    fn obstacle_sprite(&self, obstacle: &Obstacle) -> (&ugli::Texture, bool) {
+        self.obstacle_type_sprite(
+            obstacle.obstacle_type,
+            obstacle.animation_time,
+            obstacle.velocity.x < Coord::ZERO,
+        )
     }
 
-    fn draw_ui(&mut self, model: &Model, framebuffer: &mut ugli::Framebuffer) {
-        let framebuffer_size = framebuffer.size().map(|x| x as f32);
-        let screen = |anchor: Vec2<f32>, offset: Vec2<f32>| -> Vec2<f32> {
-            framebuffer_size * anchor + offset
+    /// Returns the frame of the sprite of an obstacle flying in the given direction
+    /// and whether it should be mirrored.
+    fn obstacle_type_sprite(
+        &self,
+        obstacle_type: ObstacleType,
+        animation_time: Time,
+        flying_left: bool,
+    ) -> (&ugli::Texture, bool) {
+        let mut mirror = flying_left;
+        let texture = match obstacle_type {
+            ObstacleType::Plane => self.assets.sprites.airplane.get_frame(animation_time),
+            ObstacleType::Helicopter1 => self.assets.sprites.helicopter.get_frame(animation_time),
+            ObstacleType::Helicopter2 => {
+                mirror = !mirror;
+                self.assets.sprites.helicopter2.get_frame(animation_time)
+            }
         };
+        (texture, mirror)
+    }
+
+    fn world_to_screen(&self, framebuffer_size: Vec2<f32>, position: Vec2<f32>) -> Vec2<f32> {
+        let scale = framebuffer_size.y / self.camera.fov;
+        framebuffer_size / 2.0 + (position - self.camera.center) * scale
+    }
+
+    fn draw_warnings(&self, model: &Model, layout: &Layout, framebuffer: &mut ugli::Framebuffer) {
+        let framebuffer_size = layout.framebuffer_size();
+        let view_width = self.camera.fov * framebuffer_size.x / framebuffer_size.y;
+
+        // Scheduled obstacles and the ones that have not yet entered the view
+        let incoming = model
+            .incoming_obstacles
+            .iter()
+            .map(|incoming| &incoming.obstacle)
+            .chain(model.obstacles.iter().filter(|obstacle| {
+                let x = obstacle.position.x.as_f32() - self.camera.center.x;
+                x.abs() - obstacle.radius.as_f32() > view_width / 2.0
+                    && x * obstacle.velocity.x.as_f32() < 0.0
+            }));
+
+        let size = layout.size(30.0);
+        let margin = layout.size(10.0);
+        let alpha = 0.7 + 0.3 * (self.time * 10.0).sin();
+        for obstacle in incoming {
+            let position =
+                self.world_to_screen(framebuffer_size, obstacle.position.map(|x| x.as_f32()));
+            let side = if position.x > framebuffer_size.x / 2.0 {
+                1.0
+            } else {
+                -1.0
+            };
+            let edge = if side > 0.0 { framebuffer_size.x } else { 0.0 };
+            let y = position
+                .y
+                .clamp(size + margin, framebuffer_size.y - size - margin);
+
+            // Arrow pointing at the incoming side
+            let tip = vec2(edge - side * margin, y);
+            let base = tip.x - side * size * 0.6;
+            let arrow = draw_2d::Polygon::new(
+                vec![tip, vec2(base, y + size * 0.5), vec2(base, y - size * 0.5)],
+                Rgba::new(1.0, 0.2, 0.2, alpha),
+            );
+            geng::Draw2d::draw_2d(&arrow, &self.geng, framebuffer, &geng::PixelPerfectCamera);
+
+            // Obstacle icon
+            let (texture, mirror) = self.obstacle_sprite(obstacle);
+            let mut aabb = AABB::point(vec2(base - side * size, y)).extend_uniform(size * 0.8);
+            if mirror {
+                std::mem::swap(&mut aabb.x_min, &mut aabb.x_max);
+            }
+            let quad =
+                draw_2d::TexturedQuad::colored(aabb, texture, Rgba::new(1.0, 1.0, 1.0, alpha));
+            geng::Draw2d::draw_2d(&quad, &self.geng, framebuffer, &geng::PixelPerfectCamera);
+        }
+    }
+
+    /// Draws a ring that shrinks as the next balloon pop gets closer.
+    fn draw_pop_timer(
+        &self,
+        model: &Model,
+        player: &Player,
+        layout: &Layout,
+        position: Vec2<f32>,
+        framebuffer: &mut ugli::Framebuffer,
+    ) {
+        let radius = layout.size(30.0);
+        let warning = player.next_pop < model.config.pop_warning_time;
+        let color = if warning {
+            Rgba::new(0.9, 0.2, 0.2, 1.0)
+        } else {
+            TEXT_COLOR
+        };
+
+        let circle = draw_2d::Ellipse::circle(position, radius, Rgba::new(1.0, 1.0, 1.0, 0.5));
+        geng::Draw2d::draw_2d(&circle, &self.geng, framebuffer, &geng::PixelPerfectCamera);
+
+        let progress = (player.next_pop / model.config.balloon_pop_time)
+            .as_f32()
+            .clamp(0.0, 1.0);
+        let resolution = 32;
+        let points: Vec<Vec2<f32>> = (0..=resolution)
+            .map(|i| {
+                let angle = f32::PI / 2.0 - progress * f32::PI * 2.0 * i as f32 / resolution as f32;
+                position + vec2(angle.cos(), angle.sin()) * radius
+            })
+            .collect();
+        let chain = draw_2d::Chain::new(points, layout.size(6.0), color, 1);
+        geng::Draw2d::draw_2d(&chain, &self.geng, framebuffer, &geng::PixelPerfectCamera);
+
+        let font = &**self.geng.default_font();
+        let text = format!("{}", player.next_pop.as_f32().ceil().max(0.0) as u64);
+        let text = draw_2d::Text::unit(font, text, color)
+            .scale_uniform(layout.size(15.0))
+            .align_bounding_box(vec2(0.5, 0.5))
+            .translate(position);
+        geng::Draw2d::draw_2d(&text, &self.geng, framebuffer, &geng::PixelPerfectCamera);
+    }
+
+    fn draw_ui(
+        &mut self,
+        model: &Model,
+        statistics: &RunStatistics,
+        framebuffer: &mut ugli::Framebuffer,
+    ) {
+        let layout = Layout::new(framebuffer.size().map(|x| x as f32));
         let font = &**self.geng.default_font();
 
-        if model.player.alive && !model.player.balloons.is_empty() {
+        let multiplayer = model.players.len() > 1;
+        let completed = model
+            .level
+            .as_ref()
+            .and_then(|level| level.completed.map(|result| (&level.level, result)));
+        if let Some((level, result)) = completed {
+            self.draw_level_completed(level, result, &layout, framebuffer);
+        } else if let Some(time) = model.completed {
+            self.draw_goal_reached(model, time, &layout, framebuffer);
+        } else if !model.is_over() {
             // Score
-            let text = format!("Score: {}", model.score);
-            let text = draw_2d::Text::unit(font, text, TEXT_COLOR)
-                .scale_uniform(20.0)
-                .align_bounding_box(vec2(0.0, 1.0))
-                .translate(screen(vec2(0.0, 1.0), vec2(20.0, -20.0)));
-            geng::Draw2d::draw_2d(&text, &self.geng, framebuffer, &geng::PixelPerfectCamera);
+            let mut lines = Vec::new();
+            if multiplayer {
+                for player in &model.players {
+                    let text = format!("P{}: {}", player.index + 1, player.score);
+                    lines.push((text, player_color(model, player)));
+                }
+                if model.setup.multiplayer == Multiplayer::Coop {
+                    lines.push((format!("Team: {}", model.score), TEXT_COLOR));
+                }
+            } else {
+                lines.push((format!("Score: {}", model.score), TEXT_COLOR));
+            }
+            for ghost in &model.ghosts {
+                lines.push((format!("{}: {}", ghost.label, ghost.score), TEXT_COLOR));
+            }
+            if let Some(level) = &model.level {
+                let left =
+                    (level.level.finish_height - model.focus_player().position.y).max(Coord::ZERO);
+                lines.push((format!("Time: {:.1}", level.time.as_f32()), TEXT_COLOR));
+                lines.push((format!("To the finish: {:.0}", left.as_f32()), TEXT_COLOR));
+            }
+            let modes = &model.config.modes;
+            match model.setup.mode {
+                GameMode::TimeAttack => {
+                    let height = model.focus_player().position.y.max(Coord::ZERO);
+                    lines.push((format!("Time: {:.1}", model.time.as_f32()), TEXT_COLOR));
+                    lines.push((
+                        format!(
+                            "Height: {:.0} / {:.0}",
+                            height.as_f32(),
+                            modes.time_attack_height.as_f32()
+                        ),
+                        TEXT_COLOR,
+                    ));
+                }
+                GameMode::Survival => {
+                    let text = format!(
+                        "Survived: {:.1} / {:.0}",
+                        model.time.as_f32(),
+                        modes.survival_time.as_f32()
+                    );
+                    lines.push((text, TEXT_COLOR));
+                }
+                _ => {}
+            }
+            if let Some(ghost) = &model.best_run_ghost {
+                // Height ahead or behind the best run at the same time
+                let delta = (model.players[0].position.y - ghost.position.y).as_f32();
+                let color = if delta >= 0.0 {
+                    Rgba::from_rgb(0.0, 0.5, 0.0)
+                } else {
+                    Rgba::from_rgb(0.7, 0.0, 0.0)
+                };
+                lines.push((format!("Best run: {delta:+.1}"), color));
+            }
+            let score_lines = lines.len();
+            for (i, (text, color)) in lines.into_iter().enumerate() {
+                // White text would be unreadable against the sky
+                let color = if color == Rgba::WHITE {
+                    TEXT_COLOR
+                } else {
+                    color
+                };
+                let text = draw_2d::Text::unit(font, text, color)
+                    .scale_uniform(layout.size(20.0))
+                    .align_bounding_box(vec2(0.0, 1.0))
+                    .translate(layout.anchor(vec2(0.0, 1.0), vec2(20.0, -20.0 - 45.0 * i as f32)));
+                geng::Draw2d::draw_2d(&text, &self.geng, framebuffer, &geng::PixelPerfectCamera);
+            }
 
             // High score
-            let text = format!("High Score: {}", model.high_score);
+            let text = match model.setup.mode {
+                GameMode::Endless => format!("High Score: {}", model.high_score),
+                GameMode::Daily(date) => format!("Daily {date} Best: {}", model.high_score),
+                GameMode::Race { .. } => format!("Racers: {}", model.ghosts.len() + 1),
+                GameMode::Level(_) => match &model.level {
+                    Some(level) => level.level.name.clone(),
+                    None => String::new(),
+                },
+                GameMode::TimeAttack => match model.best_time {
+                    Some(time) => format!("Best time: {:.1}", time.as_f32()),
+                    None => "Best time: -".to_owned(),
+                },
+                GameMode::Survival => format!("Best: {} s", model.high_score),
+                GameMode::NoPickups => format!("No pickups best: {}", model.high_score),
+                GameMode::Practice => {
+                    let pop_timer = if model.pop_timer { "on" } else { "off" };
+                    format!("Practice, pop timer {pop_timer}")
+                }
+            };
             let text = draw_2d::Text::unit(font, text, TEXT_COLOR)
-                .scale_uniform(20.0)
+                .scale_uniform(layout.size(20.0))
                 .align_bounding_box(vec2(1.0, 1.0))
-                .translate(screen(vec2(1.0, 1.0), vec2(-20.0, -20.0)));
+                .translate(layout.anchor(vec2(1.0, 1.0), vec2(-20.0, -20.0)));
             geng::Draw2d::draw_2d(&text, &self.geng, framebuffer, &geng::PixelPerfectCamera);
+
+            // Pop timers
+            let count = model.players.len();
+            for player in model.active_players() {
+                let offset = 100.0 * (player.index as f32 - (count - 1) as f32 / 2.0);
+                self.draw_pop_timer(
+                    model,
+                    player,
+                    &layout,
+                    layout.anchor(vec2(0.5, 1.0), vec2(offset, -50.0)),
+                    framebuffer,
+                );
+            }
+
+            // Active effects
+            let effects = model.players.iter().flat_map(|player| {
+                player
+                    .effects
+                    .iter()
+                    .map(move |effect| (player.index, effect))
+            });
+            for (i, (player, effect)) in effects.enumerate() {
+                let owner = if multiplayer {
+                    format!("P{} ", player + 1)
+                } else {
+                    String::new()
+                };
+                let text = format!(
+                    "{owner}{}: {:.1}s",
+                    power_up_name(effect.power_up_type),
+                    effect.time_left.as_f32()
+                );
+                let text = draw_2d::Text::unit(font, text, power_up_color(effect.power_up_type))
+                    .scale_uniform(layout.size(15.0))
+                    .align_bounding_box(vec2(0.0, 1.0))
+                    .translate(layout.anchor(
+                        vec2(0.0, 1.0),
+                        vec2(20.0, -25.0 - 45.0 * score_lines as f32 - 35.0 * i as f32),
+                    ));
+                geng::Draw2d::draw_2d(&text, &self.geng, framebuffer, &geng::PixelPerfectCamera);
+            }
+
+            // Score milestone
+            if let Some((score, time)) = self.milestone {
+                let alpha = (time / MILESTONE_DURATION * 2.0).min(1.0);
+                let text = format!("{score}!");
+                let text = draw_2d::Text::unit(
+                    font,
+                    text,
+                    Rgba {
+                        a: alpha,
+                        ..TEXT_COLOR
+                    },
+                )
+                .scale_uniform(layout.size(40.0))
+                .align_bounding_box(vec2(0.5, 0.5))
+                .translate(layout.anchor(vec2(0.5, 0.7), Vec2::ZERO));
+                geng::Draw2d::draw_2d(&text, &self.geng, framebuffer, &geng::PixelPerfectCamera);
+            }
         } else {
-            if !model.player.alive {
+            if multiplayer {
+                let text = match model.setup.multiplayer {
+                    Multiplayer::Versus => match model.winner() {
+                        Some(winner) => format!("Player {} wins!", winner + 1),
+                        None => "Nobody made it :(".to_owned(),
+                    },
+                    _ => "Your team is down :(".to_owned(),
+                };
+                let text = draw_2d::Text::unit(font, text, TEXT_COLOR)
+                    .scale_uniform(layout.size(40.0))
+                    .align_bounding_box(vec2(0.5, 0.5))
+                    .translate(layout.anchor(vec2(0.5, 0.5), vec2(0.0, 250.0)));
+                geng::Draw2d::draw_2d(&text, &self.geng, framebuffer, &geng::PixelPerfectCamera);
+            } else if !model.players[0].alive {
                 // Death message
-                let text = "You got hit :(";
+                let text = match statistics.death {
+                    Some(DeathCause::Fall) => "You fell too hard :(",
+                    _ => "You got hit :(",
+                };
                 let text = draw_2d::Text::unit(font, text, TEXT_COLOR)
-                    .scale_uniform(40.0)
+                    .scale_uniform(layout.size(40.0))
                     .align_bounding_box(vec2(0.5, 0.5))
-                    .translate(screen(vec2(0.5, 0.5), vec2(0.0, 250.0)));
+                    .translate(layout.anchor(vec2(0.5, 0.5), vec2(0.0, 250.0)));
                 geng::Draw2d::draw_2d(&text, &self.geng, framebuffer, &geng::PixelPerfectCamera);
             } else {
                 // Out of balloons message
                 let text = "All your balloons popped :(";
                 let text = draw_2d::Text::unit(font, text, TEXT_COLOR)
-                    .scale_uniform(40.0)
+                    .scale_uniform(layout.size(40.0))
                     .align_bounding_box(vec2(0.5, 0.5))
-                    .translate(screen(vec2(0.5, 0.5), vec2(0.0, 250.0)));
+                    .translate(layout.anchor(vec2(0.5, 0.5), vec2(0.0, 250.0)));
                 geng::Draw2d::draw_2d(&text, &self.geng, framebuffer, &geng::PixelPerfectCamera);
             }
 
             // Score
             let text = format!("You scored: {}", model.score);
             let text = draw_2d::Text::unit(font, text, TEXT_COLOR)
-                .scale_uniform(40.0)
+                .scale_uniform(layout.size(40.0))
+                .align_bounding_box(vec2(0.5, 0.5))
+                .translate(layout.anchor(vec2(0.5, 0.5), vec2(0.0, 75.0)));
+            geng::Draw2d::draw_2d(&text, &self.geng, framebuffer, &geng::PixelPerfectCamera);
+            let text = match model.setup.mode {
+                GameMode::Endless => format!("High score: {}", model.high_score),
+                GameMode::Daily(_) if !model.counts_for_record => {
+                    format!("Daily best: {} (practice run)", model.high_score)
+                }
+                GameMode::Daily(_) => format!("Daily best: {}", model.high_score),
+                GameMode::Race { .. } => {
+                    let place = 1 + model
+                        .ghosts
+                        .iter()
+                        .filter(|ghost| ghost.score > model.score)
+                        .count();
+                    format!("Place: {} of {}", place, model.ghosts.len() + 1)
+                }
+                GameMode::Level(_) => "Reach the finish to complete the level".to_owned(),
+                GameMode::TimeAttack => match model.best_time {
+                    Some(time) => format!("Best time: {:.1}", time.as_f32()),
+                    None => format!(
+                        "Reach {:.0} to set a time",
+                        model.config.modes.time_attack_height.as_f32()
+                    ),
+                },
+                GameMode::Survival => format!("Best: {} s", model.high_score),
+                GameMode::NoPickups => format!("No pickups best: {}", model.high_score),
+                GameMode::Practice => "Backspace to rewind".to_owned(),
+            };
+            let text = draw_2d::Text::unit(font, text, TEXT_COLOR)
+                .scale_uniform(layout.size(40.0))
                 .align_bounding_box(vec2(0.5, 0.5))
-                .translate(screen(vec2(0.5, 0.5), vec2(0.0, 75.0)));
+                .translate(layout.anchor(vec2(0.5, 0.5), vec2(0.0, -75.0)));
             geng::Draw2d::draw_2d(&text, &self.geng, framebuffer, &geng::PixelPerfectCamera);
-            let text = format!("High score: {}", model.high_score);
+
+            // Run statistics
+            let text = format!(
+                "Balloons grabbed: {}  Balloons popped: {}",
+                statistics.balloons_grabbed, statistics.balloons_popped
+            );
             let text = draw_2d::Text::unit(font, text, TEXT_COLOR)
-                .scale_uniform(40.0)
+                .scale_uniform(layout.size(20.0))
                 .align_bounding_box(vec2(0.5, 0.5))
-                .translate(screen(vec2(0.5, 0.5), vec2(0.0, -75.0)));
+                .translate(layout.anchor(vec2(0.5, 0.5), vec2(0.0, -160.0)));
             geng::Draw2d::draw_2d(&text, &self.geng, framebuffer, &geng::PixelPerfectCamera);
 
             // Reset prompt
             let text = "Press R to Restart";
             let text = draw_2d::Text::unit(font, text, TEXT_COLOR)
-                .scale_uniform(40.0)
+                .scale_uniform(layout.size(40.0))
                 .align_bounding_box(vec2(0.5, 0.5))
-                .translate(screen(vec2(0.5, 0.5), vec2(0.0, -250.0)));
+                .translate(layout.anchor(vec2(0.5, 0.5), vec2(0.0, -250.0)));
             geng::Draw2d::draw_2d(&text, &self.geng, framebuffer, &geng::PixelPerfectCamera);
         }
+
+        self.draw_toast(&layout, framebuffer);
+    }
+
+    /// Announces the reached goal of the time attack or the survival.
+    fn draw_goal_reached(
+        &self,
+        model: &Model,
+        time: Time,
+        layout: &Layout,
+        framebuffer: &mut ugli::Framebuffer,
+    ) {
+        let font = &**self.geng.default_font();
+        let (title, record) = match model.setup.mode {
+            GameMode::TimeAttack => {
+                let title = format!(
+                    "You reached {:.0}!",
+                    model.config.modes.time_attack_height.as_f32()
+                );
+                let record = match model.best_time {
+                    Some(best) if best <= time => format!("Best time: {:.1}", best.as_f32()),
+                    _ => "New best time!".to_owned(),
+                };
+                (title, record)
+            }
+            _ => {
+                let record = if model.score > model.high_score {
+                    "New best!".to_owned()
+                } else {
+                    format!("Best: {} s", model.high_score)
+                };
+                ("You survived!".to_owned(), record)
+            }
+        };
+        let lines = [
+            (title, 250.0),
+            (format!("Time: {:.1}", time.as_f32()), 75.0),
+            (record, -75.0),
+            ("Press R to Restart".to_owned(), -250.0),
+        ];
+        for (text, offset) in lines {
+            let text = draw_2d::Text::unit(font, text, TEXT_COLOR)
+                .scale_uniform(layout.size(40.0))
+                .align_bounding_box(vec2(0.5, 0.5))
+                .translate(layout.anchor(vec2(0.5, 0.5), vec2(0.0, offset)));
+            geng::Draw2d::draw_2d(&text, &self.geng, framebuffer, &geng::PixelPerfectCamera);
+        }
+    }
+
+    fn draw_level_completed(
+        &self,
+        level: &Level,
+        result: LevelResult,
+        layout: &Layout,
+        framebuffer: &mut ugli::Framebuffer,
+    ) {
+        let font = &**self.geng.default_font();
+        let lines = [
+            (format!("{} complete!", level.name), 250.0),
+            (format!("Time: {:.1}", result.time.as_f32()), 75.0),
+            (
+                format!("Stars: {}/{}", result.stars, level.max_stars()),
+                -75.0,
+            ),
+            ("Press R to Restart, L for the levels".to_owned(), -250.0),
+        ];
+        for (text, offset) in lines {
+            let text = draw_2d::Text::unit(font, text, TEXT_COLOR)
+                .scale_uniform(layout.size(40.0))
+                .align_bounding_box(vec2(0.5, 0.5))
+                .translate(layout.anchor(vec2(0.5, 0.5), vec2(0.0, offset)));
+            geng::Draw2d::draw_2d(&text, &self.geng, framebuffer, &geng::PixelPerfectCamera);
+        }
+    }
+
+    /// Announces the unlocked achievement.
+    fn draw_toast(&self, layout: &Layout, framebuffer: &mut ugli::Framebuffer) {
+        let (index, time) = match self.toasts.front() {
+            Some(&toast) => toast,
+            None => return,
+        };
+        let achievement = &self.assets.achievements.list[index];
+        let font = &**self.geng.default_font();
+
+        // Slide in from the bottom
+        let appear = ((TOAST_DURATION - time) * 4.0).min(time * 4.0).min(1.0);
+        let center = layout.anchor(vec2(0.5, 0.0), vec2(0.0, -40.0 + 100.0 * appear));
+        let aabb =
+            AABB::point(center).extend_symmetric(vec2(layout.size(250.0), layout.size(40.0)));
+        let quad = draw_2d::Quad::new(aabb, Rgba::new(1.0, 1.0, 1.0, 0.8));
+        geng::Draw2d::draw_2d(&quad, &self.geng, framebuffer, &geng::PixelPerfectCamera);
+
+        let text = format!("Achievement unlocked: {}", achievement.name);
+        let text = draw_2d::Text::unit(font, text, TEXT_COLOR)
+            .scale_uniform(layout.size(15.0))
+            .align_bounding_box(vec2(0.5, 0.5))
+            .translate(center + vec2(0.0, layout.size(12.0)));
+        geng::Draw2d::draw_2d(&text, &self.geng, framebuffer, &geng::PixelPerfectCamera);
+        let text = draw_2d::Text::unit(font, achievement.description.clone(), TEXT_COLOR)
+            .scale_uniform(layout.size(10.0))
+            .align_bounding_box(vec2(0.5, 0.5))
+            .translate(center - vec2(0.0, layout.size(15.0)));
+        geng::Draw2d::draw_2d(&text, &self.geng, framebuffer, &geng::PixelPerfectCamera);
+    }
+
+    /// Lists all achievements and whether they are unlocked.
+    pub fn draw_achievements(&self, model: &Model, framebuffer: &mut ugli::Framebuffer) {
+        let layout = Layout::new(framebuffer.size().map(|x| x as f32));
+        let font = &**self.geng.default_font();
+
+        let aabb = AABB::ZERO.extend_positive(layout.framebuffer_size());
+        let quad = draw_2d::Quad::new(aabb, Rgba::new(1.0, 1.0, 1.0, 0.8));
+        geng::Draw2d::draw_2d(&quad, &self.geng, framebuffer, &geng::PixelPerfectCamera);
+
+        let text = draw_2d::Text::unit(font, "Achievements", TEXT_COLOR)
+            .scale_uniform(layout.size(30.0))
+            .align_bounding_box(vec2(0.5, 1.0))
+            .translate(layout.anchor(vec2(0.5, 1.0), vec2(0.0, -40.0)));
+        geng::Draw2d::draw_2d(&text, &self.geng, framebuffer, &geng::PixelPerfectCamera);
+
+        for (i, achievement) in self.assets.achievements.list.iter().enumerate() {
+            let unlocked = model.achievements.is_unlocked(achievement);
+            let color = if unlocked {
+                TEXT_COLOR
+            } else {
+                Rgba::new(0.4, 0.4, 0.4, 1.0)
+            };
+            let mark = if unlocked { "+" } else { "-" };
+            let text = format!("{mark} {}: {}", achievement.name, achievement.description);
+            let text = draw_2d::Text::unit(font, text, color)
+                .scale_uniform(layout.size(15.0))
+                .align_bounding_box(vec2(0.5, 1.0))
+                .translate(layout.anchor(vec2(0.5, 1.0), vec2(0.0, -120.0 - 45.0 * i as f32)));
+            geng::Draw2d::draw_2d(&text, &self.geng, framebuffer, &geng::PixelPerfectCamera);
+        }
+
+        let text = draw_2d::Text::unit(font, "Press Tab to return", TEXT_COLOR)
+            .scale_uniform(layout.size(15.0))
+            .align_bounding_box(vec2(0.5, 0.0))
+            .translate(layout.anchor(vec2(0.5, 0.0), vec2(0.0, 40.0)));
+        geng::Draw2d::draw_2d(&text, &self.geng, framebuffer, &geng::PixelPerfectCamera);
+    }
+
+    /// Lists the game modes, highlighting the selected one.
+    pub fn draw_modes(
+        &self,
+        modes: &[GameMode],
+        selected: usize,
+        framebuffer: &mut ugli::Framebuffer,
+    ) {
+        let layout = Layout::new(framebuffer.size().map(|x| x as f32));
+        let font = &**self.geng.default_font();
+
+        let aabb = AABB::ZERO.extend_positive(layout.framebuffer_size());
+        let quad = draw_2d::Quad::new(aabb, Rgba::new(1.0, 1.0, 1.0, 0.8));
+        geng::Draw2d::draw_2d(&quad, &self.geng, framebuffer, &geng::PixelPerfectCamera);
+
+        let text = draw_2d::Text::unit(font, "Game modes", TEXT_COLOR)
+            .scale_uniform(layout.size(30.0))
+            .align_bounding_box(vec2(0.5, 1.0))
+            .translate(layout.anchor(vec2(0.5, 1.0), vec2(0.0, -40.0)));
+        geng::Draw2d::draw_2d(&text, &self.geng, framebuffer, &geng::PixelPerfectCamera);
+
+        let config = &self.assets.config.modes;
+        for (i, &mode) in modes.iter().enumerate() {
+            let text = match mode {
+                GameMode::Endless => "Endless: fly as high as you can".to_owned(),
+                GameMode::Daily(date) => {
+                    format!("Daily challenge {date}: the same spawns for everyone")
+                }
+                GameMode::TimeAttack => format!(
+                    "Time attack: reach {:.0} as fast as possible",
+                    config.time_attack_height.as_f32()
+                ),
+                GameMode::Survival => format!(
+                    "Survival: stay in the air for {:.0} seconds",
+                    config.survival_time.as_f32()
+                ),
+                GameMode::NoPickups => "No pickups: only the balloons you start with".to_owned(),
+                GameMode::Practice => {
+                    "Practice: no deaths, Backspace rewinds, O toggles the pop timer".to_owned()
+                }
+                GameMode::Race { .. } | GameMode::Level(_) => continue,
+            };
+            let color = if i == selected {
+                Rgba::new(0.8, 0.3, 0.0, 1.0)
+            } else {
+                TEXT_COLOR
+            };
+            let text = draw_2d::Text::unit(font, text, color)
+                .scale_uniform(layout.size(15.0))
+                .align_bounding_box(vec2(0.5, 1.0))
+                .translate(layout.anchor(vec2(0.5, 1.0), vec2(0.0, -120.0 - 45.0 * i as f32)));
+            geng::Draw2d::draw_2d(&text, &self.geng, framebuffer, &geng::PixelPerfectCamera);
+        }
+
+        let text = "Up/Down to select, Enter to play, C to return";
+        let text = draw_2d::Text::unit(font, text, TEXT_COLOR)
+            .scale_uniform(layout.size(15.0))
+            .align_bounding_box(vec2(0.5, 0.0))
+            .translate(layout.anchor(vec2(0.5, 0.0), vec2(0.0, 40.0)));
+        geng::Draw2d::draw_2d(&text, &self.geng, framebuffer, &geng::PixelPerfectCamera);
+    }
+
+    /// Lists the levels with the best results, highlighting the selected one.
+    pub fn draw_levels(
+        &self,
+        levels: &[Level],
+        records: &LevelRecords,
+        selected: usize,
+        framebuffer: &mut ugli::Framebuffer,
+    ) {
+        let layout = Layout::new(framebuffer.size().map(|x| x as f32));
+        let font = &**self.geng.default_font();
+
+        let aabb = AABB::ZERO.extend_positive(layout.framebuffer_size());
+        let quad = draw_2d::Quad::new(aabb, Rgba::new(1.0, 1.0, 1.0, 0.8));
+        geng::Draw2d::draw_2d(&quad, &self.geng, framebuffer, &geng::PixelPerfectCamera);
+
+        let text = draw_2d::Text::unit(font, "Levels", TEXT_COLOR)
+            .scale_uniform(layout.size(30.0))
+            .align_bounding_box(vec2(0.5, 1.0))
+            .translate(layout.anchor(vec2(0.5, 1.0), vec2(0.0, -40.0)));
+        geng::Draw2d::draw_2d(&text, &self.geng, framebuffer, &geng::PixelPerfectCamera);
+
+        let mut lines: Vec<String> = levels
+            .iter()
+            .enumerate()
+            .map(|(i, level)| {
+                let stars = records.stars.get(&level.name).copied().unwrap_or(0);
+                let mut text = format!(
+                    "{}. {}  Stars: {}/{}",
+                    i + 1,
+                    level.name,
+                    stars,
+                    level.max_stars()
+                );
+                if let Some(time) = records.best_times.get(&level.name) {
+                    text += &format!("  Best time: {:.1}", time.as_f32());
+                }
+                text
+            })
+            .collect();
+        lines.push("+ New level".to_owned());
+        for (i, text) in lines.into_iter().enumerate() {
+            let color = if i == selected {
+                Rgba::new(0.8, 0.3, 0.0, 1.0)
+            } else {
+                TEXT_COLOR
+            };
+            let text = draw_2d::Text::unit(font, text, color)
+                .scale_uniform(layout.size(15.0))
+                .align_bounding_box(vec2(0.5, 1.0))
+                .translate(layout.anchor(vec2(0.5, 1.0), vec2(0.0, -120.0 - 45.0 * i as f32)));
+            geng::Draw2d::draw_2d(&text, &self.geng, framebuffer, &geng::PixelPerfectCamera);
+        }
+
+        let text = "Up/Down to select, Enter to play, E to edit, L to return";
+        let text = draw_2d::Text::unit(font, text, TEXT_COLOR)
+            .scale_uniform(layout.size(15.0))
+            .align_bounding_box(vec2(0.5, 0.0))
+            .translate(layout.anchor(vec2(0.5, 0.0), vec2(0.0, 40.0)));
+        geng::Draw2d::draw_2d(&text, &self.geng, framebuffer, &geng::PixelPerfectCamera);
+    }
+
+    pub fn draw_statistics(
+        &self,
+        statistics: &LifetimeStatistics,
+        framebuffer: &mut ugli::Framebuffer,
+    ) {
+        let layout = Layout::new(framebuffer.size().map(|x| x as f32));
+        let font = &**self.geng.default_font();
+
+        let aabb = AABB::ZERO.extend_positive(layout.framebuffer_size());
+        let quad = draw_2d::Quad::new(aabb, Rgba::new(1.0, 1.0, 1.0, 0.8));
+        geng::Draw2d::draw_2d(&quad, &self.geng, framebuffer, &geng::PixelPerfectCamera);
+
+        let text = draw_2d::Text::unit(font, "Statistics", TEXT_COLOR)
+            .scale_uniform(layout.size(30.0))
+            .align_bounding_box(vec2(0.5, 1.0))
+            .translate(layout.anchor(vec2(0.5, 1.0), vec2(0.0, -40.0)));
+        geng::Draw2d::draw_2d(&text, &self.geng, framebuffer, &geng::PixelPerfectCamera);
+
+        let format_time = |seconds: f32| {
+            let seconds = seconds as u64;
+            format!("{}:{:02}", seconds / 60, seconds % 60)
+        };
+        let mut lines = vec![
+            format!("Runs: {}", statistics.total_runs),
+            format!("Altitude climbed: {:.0}", statistics.total_altitude_climbed),
+            format!("Balloons grabbed: {}", statistics.balloons_grabbed),
+            format!("Balloons popped: {}", statistics.balloons_popped),
+            format!("Play time: {}", format_time(statistics.total_play_time)),
+            format!("Longest run: {}", format_time(statistics.longest_run)),
+            format!(
+                "Deaths by running out of balloons: {}",
+                statistics.deaths_out_of_balloons
+            ),
+            format!("Deaths by falling: {}", statistics.deaths_by_falling),
+        ];
+        for obstacle in [
+            ObstacleType::Plane,
+            ObstacleType::Helicopter1,
+            ObstacleType::Helicopter2,
+        ] {
+            let deaths = statistics
+                .deaths_by_obstacle
+                .get(&obstacle)
+                .copied()
+                .unwrap_or(0);
+            lines.push(format!("Deaths by {obstacle:?}: {deaths}"));
+        }
+        for (i, line) in lines.into_iter().enumerate() {
+            let text = draw_2d::Text::unit(font, line, TEXT_COLOR)
+                .scale_uniform(layout.size(15.0))
+                .align_bounding_box(vec2(0.5, 1.0))
+                .translate(layout.anchor(vec2(0.5, 1.0), vec2(0.0, -120.0 - 45.0 * i as f32)));
+            geng::Draw2d::draw_2d(&text, &self.geng, framebuffer, &geng::PixelPerfectCamera);
+        }
+
+        let text = draw_2d::Text::unit(font, "Press E to export, I to return", TEXT_COLOR)
+            .scale_uniform(layout.size(15.0))
+            .align_bounding_box(vec2(0.5, 0.0))
+            .translate(layout.anchor(vec2(0.5, 0.0), vec2(0.0, 40.0)));
+        geng::Draw2d::draw_2d(&text, &self.geng, framebuffer, &geng::PixelPerfectCamera);
+    }
+}
+
+/// The tint telling the players apart.
+fn player_color(model: &Model, player: &Player) -> Rgba<f32> {
+    if model.players.len() == 1 {
+        return Rgba::WHITE;
+    }
+    let colors = &model.config.multiplayer.player_colors;
+    colors
+        .get(player.index % colors.len().max(1))
+        .copied()
+        .unwrap_or(Rgba::WHITE)
+}
+
+fn power_up_name(power_up_type: PowerUpType) -> &'static str {
+    match power_up_type {
+        PowerUpType::Shield => "Shield",
+        PowerUpType::PopFreeze => "Freeze",
+        PowerUpType::Magnet => "Magnet",
+        PowerUpType::SlowMotion => "Slow-mo",
+    }
+}
+
+fn power_up_color(power_up_type: PowerUpType) -> Rgba<f32> {
+    match power_up_type {
+        PowerUpType::Shield => Rgba::from_rgb(0.2, 0.4, 1.0),
+        PowerUpType::PopFreeze => Rgba::from_rgb(0.3, 0.8, 0.9),
+        PowerUpType::Magnet => Rgba::from_rgb(0.9, 0.2, 0.2),
+        PowerUpType::SlowMotion => Rgba::from_rgb(0.6, 0.3, 0.8),
     }
 }
@@ -0,0 +1,118 @@
+use super::*;
+
+/// Controls the camera following the player.
+pub struct CameraController {
+    config: CameraConfig,
+    /// The camera position without the shake.
+    center: Vec2<f32>,
+    fov: f32,
+    /// Aspect ratio of the window.
+    aspect: f32,
+    /// Current shake intensity, decays over time.
+    shake: f32,
+    shake_offset: Vec2<f32>,
+}
+
+impl CameraController {
+    pub fn new(config: &CameraConfig) -> Self {
+        Self {
+            config: config.clone(),
+            center: vec2(0.0, 2.0),
+            fov: config.fov.as_f32(),
+            aspect: ASPECT,
+            shake: 0.0,
+            shake_offset: Vec2::ZERO,
+        }
+    }
+
+    pub fn camera(&self) -> Camera2d {
+        Camera2d {
+            center: self.center + self.shake_offset,
+            fov: self.view_fov(),
+            rotation: 0.0,
+        }
+    }
+
+    pub fn set_aspect(&mut self, aspect: f32) {
+        self.aspect = aspect;
+    }
+
+    /// The actual vertical field of view, extended on narrow screens
+    /// so that the whole arena width stays visible.
+    fn view_fov(&self) -> f32 {
+        self.fov * (ASPECT / self.aspect).max(1.0)
+    }
+
+    pub fn shake(&mut self, amount: f32) {
+        self.shake = (self.shake + amount).min(self.config.max_shake.as_f32());
+    }
+
+    /// Follows the given players, framing all of them at once.
+    pub fn update(&mut self, model: &Model, followed: &[&Player], delta_time: f32) {
+        let config = &self.config;
+        let flying: Vec<&Player> = followed
+            .iter()
+            .copied()
+            .filter(|player| player.alive)
+            .collect();
+        let tracked: &[&Player] = if flying.is_empty() { followed } else { &flying };
+        let count = tracked.len().max(1) as f32;
+        let position = tracked.iter().fold(Vec2::ZERO, |acc, player| {
+            acc + player.position.map(|x| x.as_f32())
+        }) / count;
+        let velocity = tracked.iter().fold(Vec2::ZERO, |acc, player| {
+            acc + (player.velocity + player.control_velocity).map(|x| x.as_f32())
+        }) / count;
+
+        let (target, target_fov, interpolation) = if !flying.is_empty() {
+            // Zoom out at high vertical speed
+            let zoom = (velocity.y.abs() / config.zoom_speed.as_f32()).clamp(0.0, 1.0);
+            let mut fov =
+                config.fov.as_f32() + (config.max_fov.as_f32() - config.fov.as_f32()) * zoom;
+
+            // Keep everyone in view
+            let spread = flying.iter().fold(Vec2::ZERO, |spread: Vec2<f32>, player| {
+                let delta = (player.position.map(|x| x.as_f32()) - position).map(f32::abs);
+                vec2(spread.x.max(delta.x), spread.y.max(delta.y))
+            });
+            let margin = config.fov.as_f32() / 4.0;
+            fov = fov
+                .max((spread.y + margin) * 2.0)
+                .max((spread.x + margin) * 2.0 / self.aspect);
+
+            // Look ahead in the direction of movement
+            let look_ahead = (velocity * config.look_ahead.as_f32())
+                .clamp_len(..=config.max_look_ahead.as_f32());
+            let mut target = position + look_ahead + vec2(0.0, config.vertical_offset.as_f32());
+            target.x *= config.horizontal_follow.as_f32();
+            (target, fov, config.interpolation.as_f32())
+        } else {
+            // Death cam tracks the falling caterpillar
+            (
+                position,
+                config.death_fov.as_f32(),
+                config.death_interpolation.as_f32(),
+            )
+        };
+
+        self.fov += (target_fov - self.fov) / config.zoom_interpolation.as_f32() * delta_time;
+        self.center += (target - self.center) / interpolation * delta_time;
+
+        // Keep the view within the arena horizontally
+        let view_fov = self.view_fov();
+        let half_width = view_fov * self.aspect / 2.0;
+        let max_x = (model.config.arena_width.as_f32() + config.horizontal_margin.as_f32()
+            - half_width)
+            .max(0.0);
+        self.center.x = self.center.x.clamp(-max_x, max_x);
+
+        // Do not look below the ground
+        self.center.y = self.center.y.max(view_fov / 2.0 - config.fov.as_f32());
+
+        // Shake
+        self.shake = (self.shake - config.shake_decay.as_f32() * delta_time).max(0.0);
+        let mut rng = global_rng();
+        let angle = rng.gen_range(0.0..=f32::PI * 2.0);
+        self.shake_offset = vec2(angle.cos(), angle.sin()) * self.shake.sqr();
+    }
+}
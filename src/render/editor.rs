@@ -0,0 +1,123 @@
+use super::*;
+
+use crate::editor::{Editor, Selection, Tool};
+
+const SELECTION_COLOR: Rgba<f32> = Rgba {
+    r: 1.0,
+    g: 0.5,
+    b: 0.0,
+    a: 1.0,
+};
+
+impl Render {
+    /// Draws the edited level with the same sprites as the game.
+    pub fn draw_editor(&mut self, editor: &Editor, framebuffer: &mut ugli::Framebuffer) {
+        self.camera = editor.camera();
+        self.extend_backgrounds(editor.view_height + editor.fov);
+        self.draw_background(framebuffer);
+
+        // Start area
+        let street_height = self.assets.config.ground.street_height.as_f32();
+        let start_aabb = AABB::point(vec2(0.0, street_height))
+            .extend_symmetric(vec2(editor.fov * ASPECT, 0.0) / 2.0)
+            .extend_up(editor.fov);
+        for texture in self.assets.sprites.start.iter().rev() {
+            self.draw_tiled(start_aabb, texture, framebuffer);
+        }
+
+        self.draw_finish_line(editor.level.finish_height.as_f32(), framebuffer);
+
+        let level = &editor.level;
+        for (selection, position) in editor.entries() {
+            let radius = match selection {
+                Selection::Obstacle(i) => {
+                    let obstacle = &level.obstacles[i];
+                    let flying_left = obstacle.side == Side::Right;
+                    let (texture, mirror) =
+                        self.obstacle_type_sprite(obstacle.obstacle_type, Time::ZERO, flying_left);
+                    let mut aabb = AABB::point(position).extend_uniform(0.75);
+                    if mirror {
+                        std::mem::swap(&mut aabb.x_min, &mut aabb.x_max);
+                    }
+                    let quad = draw_2d::TexturedQuad::new(aabb, texture);
+                    geng::Draw2d::draw_2d(&quad, &self.geng, framebuffer, &self.camera);
+                    0.75
+                }
+                Selection::Balloon(i) => {
+                    let balloon = &level.balloons[i];
+                    let config = self.assets.config.balloon_kind(balloon.kind);
+                    let radius = config.radius.as_f32() * 1.5;
+                    let color = config.color.unwrap_or_else(|| {
+                        let colors = &self.assets.config.balloon_colors;
+                        colors.first().copied().unwrap_or(Rgba::WHITE)
+                    });
+                    let aabb = AABB::point(position).extend_uniform(radius);
+                    let quad =
+                        draw_2d::TexturedQuad::colored(aabb, &self.assets.sprites.balloon, color);
+                    geng::Draw2d::draw_2d(&quad, &self.geng, framebuffer, &self.camera);
+                    radius
+                }
+                Selection::Cloud(i) => {
+                    let cloud = &level.clouds[i];
+                    let texture = &self.assets.sprites.clouds[cloud.cloud_type as usize];
+                    let aabb = AABB::point(position).extend_uniform(0.75);
+                    let quad = draw_2d::TexturedQuad::colored(
+                        aabb,
+                        texture,
+                        Rgba::from_rgb(0.7, 0.7, 0.7),
+                    );
+                    geng::Draw2d::draw_2d(&quad, &self.geng, framebuffer, &self.camera);
+                    0.75
+                }
+            };
+            if editor.selected == Some(selection) {
+                let aabb = AABB::point(position).extend_uniform(radius);
+                let points = vec![
+                    vec2(aabb.x_min, aabb.y_min),
+                    vec2(aabb.x_max, aabb.y_min),
+                    vec2(aabb.x_max, aabb.y_max),
+                    vec2(aabb.x_min, aabb.y_max),
+                    vec2(aabb.x_min, aabb.y_min),
+                ];
+                let chain = draw_2d::Chain::new(points, 0.05, SELECTION_COLOR, 1);
+                geng::Draw2d::draw_2d(&chain, &self.geng, framebuffer, &self.camera);
+            }
+        }
+
+        // Help
+        let layout = Layout::new(framebuffer.size().map(|x| x as f32));
+        let font = &**self.geng.default_font();
+        let tool = match editor.tool {
+            Tool::Obstacle => "Obstacle",
+            Tool::Balloon => "Balloon",
+            Tool::Cloud => "Cloud",
+        };
+        let lines = [
+            format!(
+                "Editing: {}{}",
+                level.name,
+                if editor.is_modified() { " *" } else { "" }
+            ),
+            format!(
+                "Tool: {tool}  Height: {:.0}  Finish: {:.0}",
+                editor.cursor.y,
+                level.finish_height.as_f32()
+            ),
+            editor.describe(),
+            "1/2/3 - tool, LMB - place/select/drag, RMB/Delete - remove".to_owned(),
+            "T - type, V - movement, F - flip, Q/E - speed, H - finish at cursor".to_owned(),
+            if editor.confirm_exit {
+                "Unsaved changes! Esc - exit without saving, S - save".to_owned()
+            } else {
+                "Up/Down/wheel - scroll, P - play-test from here, S - save, Esc - exit".to_owned()
+            },
+        ];
+        for (i, text) in lines.into_iter().enumerate() {
+            let text = draw_2d::Text::unit(font, text, TEXT_COLOR)
+                .scale_uniform(layout.size(15.0))
+                .align_bounding_box(vec2(0.0, 1.0))
+                .translate(layout.anchor(vec2(0.0, 1.0), vec2(20.0, -20.0 - 35.0 * i as f32)));
+            geng::Draw2d::draw_2d(&text, &self.geng, framebuffer, &geng::PixelPerfectCamera);
+        }
+    }
+}
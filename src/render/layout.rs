@@ -0,0 +1,40 @@
+use super::*;
+
+/// The resolution the UI sizes are specified for.
+const REFERENCE_SIZE: Vec2<f32> = Vec2 {
+    x: 1280.0,
+    y: 720.0,
+};
+
+/// Positions and scales the UI relative to the framebuffer size.
+pub struct Layout {
+    framebuffer_size: Vec2<f32>,
+    scale: f32,
+}
+
+impl Layout {
+    pub fn new(framebuffer_size: Vec2<f32>) -> Self {
+        let scale =
+            (framebuffer_size.x / REFERENCE_SIZE.x).min(framebuffer_size.y / REFERENCE_SIZE.y);
+        Self {
+            framebuffer_size,
+            scale,
+        }
+    }
+
+    pub fn framebuffer_size(&self) -> Vec2<f32> {
+        self.framebuffer_size
+    }
+
+    /// Converts a size at the reference resolution into pixels.
+    pub fn size(&self, size: f32) -> f32 {
+        size * self.scale
+    }
+
+    /// Returns a point relative to the anchor, where (0, 0) is the bottom-left
+    /// corner of the screen and (1, 1) is the top-right one.
+    /// The offset is specified at the reference resolution.
+    pub fn anchor(&self, anchor: Vec2<f32>, offset: Vec2<f32>) -> Vec2<f32> {
+        self.framebuffer_size * anchor + offset * self.scale
+    }
+}
@@ -0,0 +1,132 @@
+use super::*;
+
+pub struct Particles {
+    particles: Vec<Particle>,
+}
+
+struct Particle {
+    position: Vec2<f32>,
+    velocity: Vec2<f32>,
+    gravity: Vec2<f32>,
+    rotation: f32,
+    angular_velocity: f32,
+    size: f32,
+    color: Rgba<f32>,
+    sprite: ParticleSprite,
+    lifetime: f32,
+    time_left: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ParticleSprite {
+    Circle,
+    Square,
+    Star,
+    Cloud(usize),
+}
+
+/// Describes a burst of particles.
+#[derive(Debug, Clone)]
+pub struct Emitter {
+    pub amount: usize,
+    /// The base direction of the emitted particles in radians.
+    pub direction: f32,
+    /// Maximum deviation from the base direction in radians.
+    pub spread: f32,
+    pub speed: std::ops::RangeInclusive<f32>,
+    pub gravity: Vec2<f32>,
+    pub lifetime: std::ops::RangeInclusive<f32>,
+    pub size: std::ops::RangeInclusive<f32>,
+    pub spin: f32,
+    pub color: Rgba<f32>,
+    pub sprite: ParticleSprite,
+}
+
+impl Particles {
+    pub fn new() -> Self {
+        Self {
+            particles: Vec::new(),
+        }
+    }
+
+    pub fn emit(&mut self, emitter: &Emitter, position: Vec2<f32>) {
+        let mut rng = global_rng();
+        for _ in 0..emitter.amount {
+            let angle = emitter.direction + rng.gen_range(-emitter.spread..=emitter.spread);
+            let speed = rng.gen_range(emitter.speed.clone());
+            let lifetime = rng.gen_range(emitter.lifetime.clone());
+            self.particles.push(Particle {
+                position,
+                velocity: vec2(angle.cos(), angle.sin()) * speed,
+                gravity: emitter.gravity,
+                rotation: rng.gen_range(0.0..=f32::PI * 2.0),
+                angular_velocity: rng.gen_range(-emitter.spin..=emitter.spin),
+                size: rng.gen_range(emitter.size.clone()),
+                color: emitter.color,
+                sprite: emitter.sprite,
+                lifetime,
+                time_left: lifetime,
+            });
+        }
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        for particle in &mut self.particles {
+            particle.velocity += particle.gravity * delta_time;
+            particle.position += particle.velocity * delta_time;
+            particle.rotation += particle.angular_velocity * delta_time;
+            particle.time_left -= delta_time;
+        }
+        self.particles.retain(|particle| particle.time_left > 0.0);
+    }
+
+    pub fn draw(
+        &self,
+        geng: &Geng,
+        assets: &Assets,
+        framebuffer: &mut ugli::Framebuffer,
+        camera: &impl geng::AbstractCamera2d,
+    ) {
+        for particle in &self.particles {
+            let mut color = particle.color;
+            color.a *= (particle.time_left / particle.lifetime).clamp(0.0, 1.0);
+            let transform = Mat3::translate(particle.position) * Mat3::rotate(particle.rotation);
+            match particle.sprite {
+                ParticleSprite::Circle => {
+                    let circle = draw_2d::Ellipse::circle(Vec2::ZERO, particle.size, color)
+                        .transform(transform);
+                    geng::Draw2d::draw_2d(&circle, geng, framebuffer, camera);
+                }
+                ParticleSprite::Square => {
+                    let quad = draw_2d::Quad::new(AABB::ZERO.extend_uniform(particle.size), color)
+                        .transform(transform);
+                    geng::Draw2d::draw_2d(&quad, geng, framebuffer, camera);
+                }
+                ParticleSprite::Star => {
+                    let mut vertices = vec![Vec2::ZERO];
+                    for i in 0..=10 {
+                        let angle = f32::PI * 2.0 * i as f32 / 10.0;
+                        let radius = if i % 2 == 0 {
+                            particle.size
+                        } else {
+                            particle.size * 0.4
+                        };
+                        vertices.push(vec2(angle.cos(), angle.sin()) * radius);
+                    }
+                    let star = draw_2d::Polygon::new(vertices, color).transform(transform);
+                    geng::Draw2d::draw_2d(&star, geng, framebuffer, camera);
+                }
+                ParticleSprite::Cloud(index) => {
+                    let texture = &assets.sprites.clouds[index % assets.sprites.clouds.len()];
+                    let quad = draw_2d::TexturedQuad::colored(
+                        AABB::ZERO.extend_uniform(particle.size),
+                        texture,
+                        color,
+                    )
+                    .transform(transform);
+                    geng::Draw2d::draw_2d(&quad, geng, framebuffer, camera);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use super::*;
+
+const POPUP_DURATION: f32 = 1.0;
+const POPUP_RISE_SPEED: f32 = 1.0;
+const POPUP_SIZE: f32 = 0.3;
+
+/// Floating score texts rising from the place they were scored at.
+pub struct ScorePopups {
+    popups: Vec<ScorePopup>,
+}
+
+struct ScorePopup {
+    text: String,
+    position: Vec2<f32>,
+    color: Rgba<f32>,
+    time_left: f32,
+}
+
+impl ScorePopups {
+    pub fn new() -> Self {
+        Self { popups: Vec::new() }
+    }
+
+    pub fn spawn(&mut self, score: Score, combo: usize, position: Vec2<f32>) {
+        let (text, color) = if combo > 1 {
+            (format!("+{score} x{combo}"), Rgba::from_rgb(1.0, 0.6, 0.1))
+        } else {
+            (format!("+{score}"), Rgba::from_rgb(1.0, 0.9, 0.2))
+        };
+        self.popups.push(ScorePopup {
+            text,
+            position,
+            color,
+            time_left: POPUP_DURATION,
+        });
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        for popup in &mut self.popups {
+            popup.position.y += POPUP_RISE_SPEED * delta_time;
+            popup.time_left -= delta_time;
+        }
+        self.popups.retain(|popup| popup.time_left > 0.0);
+    }
+
+    pub fn draw(
+        &self,
+        geng: &Geng,
+        framebuffer: &mut ugli::Framebuffer,
+        camera: &impl geng::AbstractCamera2d,
+    ) {
+        let font = &**geng.default_font();
+        for popup in &self.popups {
+            let mut color = popup.color;
+            color.a *= (popup.time_left / POPUP_DURATION * 2.0).min(1.0);
+            // Pop in slightly bigger
+            let scale = 1.0 + (popup.time_left - POPUP_DURATION * 0.8).max(0.0) * 2.0;
+            let text = draw_2d::Text::unit(font, &popup.text, color)
+                .scale_uniform(POPUP_SIZE * scale)
+                .translate(popup.position);
+            geng::Draw2d::draw_2d(&text, geng, framebuffer, camera);
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use super::*;
+
+use model::*;
+
+const BEST_REPLAY_SAVE_FILE: &str = "caterpillar_best_run";
+
+/// The trajectory of a run, sampled at a fixed interval.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Replay {
+    pub score: Score,
+    pub sample_interval: f32,
+    pub frames: Vec<ReplayFrame>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayFrame {
+    pub position: Vec2<Coord>,
+    pub alive: bool,
+    pub balloons: Vec<GhostBalloon>,
+}
+
+/// Records the first player's trajectory during a run.
+pub struct ReplayRecorder {
+    /// Time since the start of the run.
+    pub time: f32,
+    next_sample: f32,
+    replay: Replay,
+}
+
+impl Replay {
+    pub fn load() -> Option<Self> {
+        batbox::preferences::load(BEST_REPLAY_SAVE_FILE)
+    }
+
+    pub fn save(&self) {
+        batbox::preferences::save(BEST_REPLAY_SAVE_FILE, self);
+    }
+
+    /// The ghost following the replay at the given time since the start,
+    /// `None` once the replay has ended.
+    pub fn ghost_at(&self, time: f32) -> Option<Ghost> {
+        let t = time / self.sample_interval;
+        let index = t.floor() as usize;
+        let frame = self.frames.get(index)?;
+        let next = self.frames.get(index + 1).unwrap_or(frame);
+        let t = r32(t.fract());
+        let lerp = |a: Vec2<Coord>, b: Vec2<Coord>| a + (b - a) * t;
+
+        let balloons = if next.balloons.len() == frame.balloons.len() {
+            frame
+                .balloons
+                .iter()
+                .zip(&next.balloons)
+                .map(|(a, b)| GhostBalloon {
+                    position: lerp(a.position, b.position),
+                    ..*a
+                })
+                .collect()
+        } else {
+            // A balloon was grabbed or popped in between
+            frame.balloons.clone()
+        };
+        Some(Ghost {
+            label: "Best".to_owned(),
+            position: lerp(frame.position, next.position),
+            alive: frame.alive,
+            balloons: frame.balloons.len(),
+            score: self.score,
+            bundle: balloons,
+        })
+    }
+}
+
+impl ReplayRecorder {
+    pub fn new() -> Self {
+        Self {
+            time: 0.0,
+            next_sample: 0.0,
+            replay: Replay::default(),
+        }
+    }
+
+    pub fn update(&mut self, model: &Model, delta_time: f32) {
+        if model.is_over() {
+            return;
+        }
+        let interval = model.config.ghosts.sample_interval.as_f32();
+        self.replay.sample_interval = interval;
+        self.replay.score = model.score;
+        self.time += delta_time;
+        while self.next_sample <= self.time {
+            self.next_sample += interval;
+            let player = &model.players[0];
+            self.replay.frames.push(ReplayFrame {
+                position: player.position,
+                alive: player.alive,
+                balloons: player
+                    .balloons
+                    .iter()
+                    .filter_map(|id| model.balloons.get(id))
+                    .map(|balloon| GhostBalloon {
+                        position: balloon.position,
+                        radius: balloon.radius,
+                        color: balloon.color,
+                    })
+                    .collect(),
+            });
+        }
+    }
+
+    /// Takes the recorded run, `None` if nothing was recorded.
+    pub fn finish(&mut self) -> Option<Replay> {
+        if self.replay.frames.is_empty() {
+            return None;
+        }
+        Some(std::mem::take(&mut self.replay))
+    }
+}
@@ -0,0 +1,156 @@
+use super::*;
+
+use model::*;
+
+const LIFETIME_STATISTICS_SAVE_FILE: &str = "caterpillar_statistics";
+const EXPORT_FILE: &str = "caterpillar_statistics.json";
+
+/// Statistics of the current run collected from the gameplay events.
+#[derive(Debug, Clone, Default)]
+pub struct RunStatistics {
+    pub balloons_grabbed: usize,
+    pub balloons_popped: usize,
+    pub altitude_climbed: f32,
+    pub play_time: f32,
+    /// How the run ended, `None` while it is still going.
+    pub death: Option<DeathCause>,
+    /// Whether the run ended by reaching the goal of the mode or the level.
+    pub completed: bool,
+    /// What killed a player last.
+    last_death: Option<DeathCause>,
+    /// The height of each player in the previous frame.
+    last_heights: Vec<f32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeathCause {
+    Obstacle(ObstacleType),
+    /// Landed too hard.
+    Fall,
+    /// Landed on the ground without any balloons left.
+    OutOfBalloons,
+}
+
+/// Statistics accumulated over all runs, persisted across sessions.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LifetimeStatistics {
+    pub total_runs: usize,
+    pub total_altitude_climbed: f32,
+    pub balloons_grabbed: usize,
+    pub balloons_popped: usize,
+    pub deaths_by_obstacle: HashMap<ObstacleType, usize>,
+    pub deaths_out_of_balloons: usize,
+    pub deaths_by_falling: usize,
+    /// Total play time in seconds.
+    pub total_play_time: f32,
+    /// Duration of the longest run in seconds.
+    pub longest_run: f32,
+}
+
+impl RunStatistics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.death.is_some() || self.completed
+    }
+
+    pub fn handle_event(&mut self, event: &Event) {
+        match *event {
+            Event::BalloonPopped { .. } => self.balloons_popped += 1,
+            Event::BalloonGrabbed { .. } => self.balloons_grabbed += 1,
+            Event::PlayerHit {
+                shielded, obstacle, ..
+            } => {
+                if !shielded {
+                    self.last_death = Some(DeathCause::Obstacle(obstacle));
+                }
+            }
+            Event::PlayerFell { .. } => self.last_death = Some(DeathCause::Fall),
+            Event::PowerUpCollected { .. }
+            | Event::ObstacleSpawned { .. }
+            | Event::NearMiss { .. }
+            | Event::PlayerLanded { .. }
+            | Event::BalloonStolen { .. }
+            | Event::ScoreMilestone { .. }
+            | Event::BonusScore { .. }
+            | Event::AchievementUnlocked { .. } => {}
+        }
+    }
+
+    pub fn update(&mut self, model: &Model, delta_time: f32) {
+        if self.is_over() {
+            return;
+        }
+        if model.is_completed() {
+            self.completed = true;
+            return;
+        }
+        if model.is_over() {
+            self.death = Some(self.last_death.unwrap_or(DeathCause::OutOfBalloons));
+            return;
+        }
+
+        self.play_time += delta_time;
+        if self.last_heights.len() != model.players.len() {
+            // The run can start high up, like a resumed run or a play-test
+            self.last_heights = model
+                .players
+                .iter()
+                .map(|player| player.position.y.as_f32())
+                .collect();
+        }
+        for (player, last_height) in model.players.iter().zip(&mut self.last_heights) {
+            let height = player.position.y.as_f32();
+            self.altitude_climbed += (height - *last_height).max(0.0);
+            *last_height = height;
+        }
+    }
+}
+
+impl LifetimeStatistics {
+    pub fn load() -> Self {
+        batbox::preferences::load(LIFETIME_STATISTICS_SAVE_FILE).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        batbox::preferences::save(LIFETIME_STATISTICS_SAVE_FILE, self);
+    }
+
+    /// Adds a finished (or abandoned) run to the totals.
+    pub fn record(&mut self, run: &RunStatistics) {
+        self.total_runs += 1;
+        self.total_altitude_climbed += run.altitude_climbed;
+        self.balloons_grabbed += run.balloons_grabbed;
+        self.balloons_popped += run.balloons_popped;
+        match run.death {
+            Some(DeathCause::Obstacle(obstacle)) => {
+                *self.deaths_by_obstacle.entry(obstacle).or_default() += 1;
+            }
+            Some(DeathCause::Fall) => self.deaths_by_falling += 1,
+            Some(DeathCause::OutOfBalloons) => self.deaths_out_of_balloons += 1,
+            None => {}
+        }
+        self.total_play_time += run.play_time;
+        self.longest_run = self.longest_run.max(run.play_time);
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Failed to serialize statistics")
+    }
+
+    /// Writes the statistics as JSON to the working directory,
+    /// or logs them on the web.
+    pub fn export(&self) {
+        let json = self.to_json();
+        #[cfg(not(target_arch = "wasm32"))]
+        match std::fs::write(EXPORT_FILE, &json) {
+            Ok(()) => info!("Exported statistics to {EXPORT_FILE}"),
+            Err(err) => error!("Failed to export statistics: {err}"),
+        }
+        #[cfg(target_arch = "wasm32")]
+        info!("{EXPORT_FILE}:\n{json}");
+    }
+}